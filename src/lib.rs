@@ -17,8 +17,8 @@
 //! This is not intended to be an exhaustive list, but serves as a guide to help determine whether
 //! `myn` is suitable for your use case.
 //!
-//! - Only designed to parse a small subset of Rust language syntax. Mostly just `struct` and `enum`
-//!   types.
+//! - Only designed to parse a small subset of Rust language syntax. Mostly just `struct`, `enum`,
+//!   and `union` types.
 //! - Can only be used in `proc-macro` crates. This rules out using the library in tests.
 //!
 //! # Where to begin
@@ -27,11 +27,15 @@
 //! attempting to define a one-size-fits-all strongly typed AST. The [`TokenStreamExt`] extension
 //! trait turns the `TokenStream` into a [`TokenIter`].
 //!
+//! Output is generated with the [`quote!`] macro, which interpolates any type implementing
+//! [`ToTokens`].
+//!
 //! The [`onlyargs`] and [`onlyerror`] crates are good examples of how to use the library.
 //!
 //! [benchmarks]: https://github.com/parasyte/myn/blob/main/benchmarks.md
 //! [`onlyargs`]: https://github.com/parasyte/onlyargs
 //! [`onlyerror`]: https://github.com/parasyte/onlyerror
+//! [`ToTokens`]: crate::traits::ToTokens
 //! [`TokenIter`]: crate::ty::TokenIter
 //! [`TokenStream`]: proc_macro::TokenStream
 //! [`TokenStreamExt`]: crate::traits::TokenStreamExt
@@ -44,7 +48,15 @@
 #[cfg(not(test))]
 extern crate proc_macro;
 
+mod macros;
 pub mod prelude;
+pub mod token;
 pub mod traits;
 pub mod ty;
 pub mod utils;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::macros::*;
+    pub use proc_macro::{Delimiter, Span, TokenStream};
+}
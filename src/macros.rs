@@ -0,0 +1,606 @@
+//! Code generation macros.
+//!
+//! The [`quote!`] and [`quote_spanned!`] macros are implemented with `macro_rules!` so they do not
+//! add a proc-macro dependency. Everything in this module except the macros is an implementation
+//! detail and is only public for use by the macro expansions.
+
+use crate::traits::{IdentFragment, ToTokens};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::ops::{BitOr, Deref};
+
+/// Build a [`TokenStream`] from Rust syntax, with variable interpolation.
+///
+/// Any value implementing [`ToTokens`] can be interpolated with `#var`. Repetition is written as
+/// `#(...)*` or with a separator as `#(...),*`. Every variable interpolated inside a repetition
+/// must be iterable (e.g. an iterator, slice, or `Vec`). Use [`std::iter::repeat`] to repeat a
+/// single value.
+///
+/// All tokens are created with [`Span::call_site`]. See [`quote_spanned!`] for using a different
+/// span.
+///
+/// Large inputs may require raising the `recursion_limit` in the calling crate.
+///
+/// # Example
+///
+/// ```ignore
+/// use myn::prelude::*;
+/// use myn::quote;
+///
+/// let name = input.try_ident()?;
+/// let fields = vec![/* ... */];
+///
+/// let output = quote! {
+///     impl #name {
+///         pub fn field_names() -> &'static [&'static str] {
+///             &[#(#fields),*]
+///         }
+///     }
+/// };
+/// ```
+///
+/// [`ToTokens`]: crate::traits::ToTokens
+/// [`Span::call_site`]: proc_macro::Span::call_site
+/// [`TokenStream`]: proc_macro::TokenStream
+/// [`quote_spanned!`]: crate::quote_spanned
+#[macro_export]
+macro_rules! quote {
+    ($($tt:tt)*) => {
+        $crate::quote_spanned!($crate::__private::Span::call_site() => $($tt)*)
+    };
+}
+
+/// Build a [`TokenStream`] like [`quote!`], with all tokens created using the given span.
+///
+/// Interpolated values keep their own spans.
+///
+/// # Example
+///
+/// ```ignore
+/// use myn::quote_spanned;
+///
+/// let span = name.span();
+/// let output = quote_spanned!(span=> compile_error!("Unsupported field type"););
+/// ```
+///
+/// [`TokenStream`]: proc_macro::TokenStream
+#[macro_export]
+macro_rules! quote_spanned {
+    ($span:expr => $($tt:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut _s = $crate::__private::TokenStream::new();
+        let _span: $crate::__private::Span = $span;
+        $crate::__quote_each_token!(_s _span $($tt)*);
+        _s
+    }};
+}
+
+/// Create an [`Ident`] from a format string.
+///
+/// Arguments are formatted with [`IdentFragment`], which strips the `r#` prefix from raw
+/// identifiers. The resulting identifier is created with [`new_ident`], so keywords become raw
+/// identifiers and invalid identifiers are reported as errors.
+///
+/// The span is taken from the first argument that has one (i.e. the first [`Ident`]), or
+/// [`Span::call_site`] otherwise. It can be chosen explicitly with a trailing `span = ...`
+/// argument.
+///
+/// Evaluates to `Result<Ident, TokenStream>`.
+///
+/// # Example
+///
+/// ```ignore
+/// use myn::format_ident;
+///
+/// let builder = format_ident!("{}Builder", name)?;
+/// let getter = format_ident!("get_{}", field, span = field.span())?;
+/// ```
+///
+/// [`Ident`]: proc_macro::Ident
+/// [`IdentFragment`]: crate::traits::IdentFragment
+/// [`new_ident`]: crate::utils::new_ident
+/// [`Span::call_site`]: proc_macro::Span::call_site
+#[macro_export]
+macro_rules! format_ident {
+    ($fmt:expr $(,)?) => {
+        $crate::__format_ident!([::std::option::Option::None, $fmt] [])
+    };
+    ($fmt:expr, $($rest:tt)*) => {
+        $crate::__format_ident!([::std::option::Option::None, $fmt] [] $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __format_ident {
+    ([$span:expr, $fmt:expr] [$($args:expr,)*]) => {
+        $crate::utils::new_ident(
+            &::std::format!($fmt, $($crate::__private::IdentFragmentAdapter($args)),*),
+            $span.unwrap_or_else($crate::__private::Span::call_site),
+        )
+    };
+    ([$span:expr, $fmt:expr] [$($args:expr,)*] span = $explicit:expr $(,)?) => {
+        $crate::__format_ident!(
+            [::std::option::Option::Some::<$crate::__private::Span>($explicit), $fmt]
+            [$($args,)*]
+        )
+    };
+    ([$span:expr, $fmt:expr] [$($args:expr,)*] $arg:expr $(, $($rest:tt)*)?) => {
+        match &$arg {
+            arg => $crate::__format_ident!(
+                [$span.or_else(|| $crate::traits::IdentFragment::span(arg)), $fmt]
+                [$($args,)* arg,] $($($rest)*)?
+            ),
+        }
+    };
+}
+
+/// Visit each token with three tokens of context on either side.
+///
+/// Placeholders (`@`) pad the context, and the current token is wrapped in parentheses so it can
+/// be distinguished from padding. This keeps the recursion depth proportional to group nesting
+/// instead of input length.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_each_token {
+    ($s:ident $span:ident $($tts:tt)*) => {
+        $crate::__quote_tokens_with_context!($s $span
+            (@ @ @ @ @ @ $($tts)*)
+            (@ @ @ @ @ $($tts)* @)
+            (@ @ @ @ $($tts)* @ @)
+            (@ @ @ $(($tts))* @ @ @)
+            (@ @ $($tts)* @ @ @ @)
+            (@ $($tts)* @ @ @ @ @)
+            ($($tts)* @ @ @ @ @ @)
+        );
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_tokens_with_context {
+    ($s:ident $span:ident
+        ($($b3:tt)*) ($($b2:tt)*) ($($b1:tt)*)
+        ($($curr:tt)*)
+        ($($a1:tt)*) ($($a2:tt)*) ($($a3:tt)*)
+    ) => {
+        $(
+            $crate::__quote_token_with_context!($s $span $b3 $b2 $b1 $curr $a1 $a2 $a3);
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_token_with_context {
+    // Padding.
+    ($s:ident $span:ident $b3:tt $b2:tt $b1:tt @ $a1:tt $a2:tt $a3:tt) => {};
+
+    // Repetition without a separator: `#(...)*`
+    ($s:ident $span:ident $b3:tt $b2:tt $b1:tt (#) ( $($inner:tt)* ) * $a3:tt) => {{
+        use $crate::__private::ext::*;
+        let _has_iter = $crate::__private::ThereIsNoIteratorInRepetition;
+        $crate::__quote_bind!((into_iter _has_iter) $($inner)*);
+        let _: $crate::__private::HasIterator = _has_iter;
+        loop {
+            $crate::__quote_bind!(next $($inner)*);
+            $crate::__quote_each_token!($s $span $($inner)*);
+        }
+    }};
+    ($s:ident $span:ident $b3:tt $b2:tt # (( $($inner:tt)* )) * $a2:tt $a3:tt) => {};
+    ($s:ident $span:ident $b3:tt # ( $($inner:tt)* ) (*) $a1:tt $a2:tt $a3:tt) => {};
+
+    // Repetition with a separator: `#(...),*`
+    ($s:ident $span:ident $b3:tt $b2:tt $b1:tt (#) ( $($inner:tt)* ) $sep:tt *) => {{
+        use $crate::__private::ext::*;
+        let _has_iter = $crate::__private::ThereIsNoIteratorInRepetition;
+        $crate::__quote_bind!((into_iter _has_iter) $($inner)*);
+        let _: $crate::__private::HasIterator = _has_iter;
+        let mut _first = true;
+        loop {
+            $crate::__quote_bind!(next $($inner)*);
+            if !_first {
+                $crate::__quote_token!($s $span $sep);
+            }
+            _first = false;
+            $crate::__quote_each_token!($s $span $($inner)*);
+        }
+    }};
+    ($s:ident $span:ident $b3:tt $b2:tt # (( $($inner:tt)* )) $sep:tt * $a3:tt) => {};
+    ($s:ident $span:ident $b3:tt # ( $($inner:tt)* ) ($sep:tt) * $a2:tt $a3:tt) => {};
+    ($s:ident $span:ident # ( $($inner:tt)* ) $sep:tt (*) $a1:tt $a2:tt $a3:tt) => {};
+
+    // Interpolation: `#var`
+    ($s:ident $span:ident $b3:tt $b2:tt $b1:tt (#) $var:ident $a2:tt $a3:tt) => {
+        $crate::traits::ToTokens::to_tokens(&$var, &mut $s);
+    };
+    ($s:ident $span:ident $b3:tt $b2:tt # ($var:ident) $a1:tt $a2:tt $a3:tt) => {};
+
+    // Everything else is emitted as-is.
+    ($s:ident $span:ident $b3:tt $b2:tt $b1:tt ($curr:tt) $a1:tt $a2:tt $a3:tt) => {
+        $crate::__quote_token!($s $span $curr);
+    };
+}
+
+/// Bind each variable interpolated in a repetition, either as an iterator or as the next item.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_bind {
+    ($mode:tt $($tts:tt)*) => {
+        $crate::__quote_bind_with_context!($mode (@ $(($tts))*) ($($tts)* @));
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_bind_with_context {
+    ($mode:tt ($($curr:tt)*) ($($a1:tt)*)) => {
+        $(
+            $crate::__quote_bind_token!($mode $curr $a1);
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_bind_token {
+    ((into_iter $has_iter:ident) (#) $var:ident) => {
+        #[allow(unused_mut)]
+        let (mut $var, i) = $var.quote_into_iter();
+        let $has_iter = $has_iter | i;
+    };
+    (next (#) $var:ident) => {
+        let $var = match $var.next() {
+            Some(_x) => $crate::__private::RepInterp(_x),
+            None => break,
+        };
+    };
+    ($mode:tt (( $($inner:tt)* )) $a1:tt) => {
+        $crate::__quote_bind!($mode $($inner)*);
+    };
+    ($mode:tt ([ $($inner:tt)* ]) $a1:tt) => {
+        $crate::__quote_bind!($mode $($inner)*);
+    };
+    ($mode:tt ({ $($inner:tt)* }) $a1:tt) => {
+        $crate::__quote_bind!($mode $($inner)*);
+    };
+    ($mode:tt $curr:tt $a1:tt) => {};
+}
+
+/// Emit a single token.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_token {
+    ($s:ident $span:ident ( $($inner:tt)* )) => {
+        $crate::__private::push_group(
+            &mut $s,
+            $span,
+            $crate::__private::Delimiter::Parenthesis,
+            $crate::quote_spanned!($span=> $($inner)*),
+        );
+    };
+    ($s:ident $span:ident [ $($inner:tt)* ]) => {
+        $crate::__private::push_group(
+            &mut $s,
+            $span,
+            $crate::__private::Delimiter::Bracket,
+            $crate::quote_spanned!($span=> $($inner)*),
+        );
+    };
+    ($s:ident $span:ident { $($inner:tt)* }) => {
+        $crate::__private::push_group(
+            &mut $s,
+            $span,
+            $crate::__private::Delimiter::Brace,
+            $crate::quote_spanned!($span=> $($inner)*),
+        );
+    };
+    ($s:ident $span:ident $ident:ident) => {
+        $crate::__private::push_ident(&mut $s, $span, stringify!($ident));
+    };
+    ($s:ident $span:ident $lifetime:lifetime) => {
+        $crate::__private::push_lifetime(&mut $s, $span, stringify!($lifetime));
+    };
+    ($s:ident $span:ident $lit:literal) => {
+        $crate::__private::push_literal(&mut $s, $span, stringify!($lit));
+    };
+    ($s:ident $span:ident $punct:tt) => {
+        $crate::__private::push_punct(&mut $s, $span, stringify!($punct));
+    };
+}
+
+/// Method-resolution helpers for iterating over interpolated variables in repetitions.
+pub mod ext {
+    use super::HasIterator;
+
+    /// Iterators are used as-is.
+    pub trait RepIteratorExt: Iterator + Sized {
+        fn quote_into_iter(self) -> (Self, HasIterator) {
+            (self, HasIterator)
+        }
+    }
+
+    impl<T: Iterator> RepIteratorExt for T {}
+
+    /// Collections are iterated by reference.
+    pub trait RepAsIteratorExt<'q> {
+        type Iter: Iterator;
+
+        fn quote_into_iter(&'q self) -> (Self::Iter, HasIterator);
+    }
+
+    impl<'q, T: 'q> RepAsIteratorExt<'q> for [T] {
+        type Iter = std::slice::Iter<'q, T>;
+
+        fn quote_into_iter(&'q self) -> (Self::Iter, HasIterator) {
+            (self.iter(), HasIterator)
+        }
+    }
+
+    impl<'q, T: 'q> RepAsIteratorExt<'q> for Vec<T> {
+        type Iter = std::slice::Iter<'q, T>;
+
+        fn quote_into_iter(&'q self) -> (Self::Iter, HasIterator) {
+            (self.iter(), HasIterator)
+        }
+    }
+
+    impl<'q, T: 'q, const N: usize> RepAsIteratorExt<'q> for [T; N] {
+        type Iter = std::slice::Iter<'q, T>;
+
+        fn quote_into_iter(&'q self) -> (Self::Iter, HasIterator) {
+            (self.iter(), HasIterator)
+        }
+    }
+
+    impl<'q, T: 'q> RepAsIteratorExt<'q> for Option<T> {
+        type Iter = std::option::Iter<'q, T>;
+
+        fn quote_into_iter(&'q self) -> (Self::Iter, HasIterator) {
+            (self.iter(), HasIterator)
+        }
+    }
+}
+
+/// Marker for a repetition containing at least one iterable variable.
+pub struct HasIterator;
+
+/// Marker for a repetition without any iterable variables.
+///
+/// This name shows up in the type error when a repetition would loop forever.
+pub struct ThereIsNoIteratorInRepetition;
+
+impl BitOr<HasIterator> for ThereIsNoIteratorInRepetition {
+    type Output = HasIterator;
+
+    fn bitor(self, _rhs: HasIterator) -> HasIterator {
+        HasIterator
+    }
+}
+
+impl BitOr<HasIterator> for HasIterator {
+    type Output = HasIterator;
+
+    fn bitor(self, _rhs: HasIterator) -> HasIterator {
+        HasIterator
+    }
+}
+
+/// The current item of a variable within a repetition.
+pub struct RepInterp<T>(pub T);
+
+impl<T> RepInterp<T> {
+    // Variables appearing more than once in a repetition are only advanced once per iteration.
+    #[allow(clippy::should_implement_trait, clippy::unnecessary_wraps)]
+    pub fn next(self) -> Option<T> {
+        Some(self.0)
+    }
+}
+
+impl<T> Deref for RepInterp<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ToTokens> ToTokens for RepInterp<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens);
+    }
+}
+
+/// Formats an [`IdentFragment`] with [`std::fmt::Display`].
+pub struct IdentFragmentAdapter<T>(pub T);
+
+impl<T: IdentFragment> std::fmt::Display for IdentFragmentAdapter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        IdentFragment::fmt(&self.0, f)
+    }
+}
+
+pub fn push_group(tokens: &mut TokenStream, span: Span, delim: Delimiter, inner: TokenStream) {
+    let mut group = Group::new(delim, inner);
+    group.set_span(span);
+    tokens.extend([TokenTree::Group(group)]);
+}
+
+pub fn push_ident(tokens: &mut TokenStream, span: Span, ident: &str) {
+    let ident = match ident.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(raw, span),
+        None => Ident::new(ident, span),
+    };
+    tokens.extend([TokenTree::Ident(ident)]);
+}
+
+pub fn push_lifetime(tokens: &mut TokenStream, span: Span, lifetime: &str) {
+    let mut apostrophe = Punct::new('\'', Spacing::Joint);
+    apostrophe.set_span(span);
+    tokens.extend([TokenTree::Punct(apostrophe)]);
+    push_ident(tokens, span, lifetime.trim_start_matches('\''));
+}
+
+/// # Panics
+///
+/// Panics if the input is not a valid literal. The macro only passes literal tokens.
+pub fn push_literal(tokens: &mut TokenStream, span: Span, lit: &str) {
+    let mut lit: Literal = lit.parse().expect("Invalid literal");
+    lit.set_span(span);
+    tokens.extend([TokenTree::Literal(lit)]);
+}
+
+pub fn push_punct(tokens: &mut TokenStream, span: Span, punct: &str) {
+    let last = punct.chars().count() - 1;
+    tokens.extend(punct.chars().enumerate().map(|(i, ch)| {
+        let spacing = if i == last {
+            Spacing::Alone
+        } else {
+            Spacing::Joint
+        };
+        let mut punct = Punct::new(ch, spacing);
+        punct.set_span(span);
+
+        TokenTree::Punct(punct)
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::traits::TokenStreamExt as _;
+    use proc_macro::{Ident, Spacing, Span, TokenTree};
+
+    #[test]
+    fn test_quote_tokens() {
+        let output = quote! {
+            impl<'a> Foo for r#type<'a> {
+                fn foo(&self) -> &'static str { "foo" }
+            }
+        };
+
+        assert_eq!(
+            output.to_string(),
+            "impl < 'a > Foo for r#type < 'a > { fn foo (& self) -> & 'static str { \"foo\" } }"
+        );
+    }
+
+    #[test]
+    fn test_quote_punct_spacing() {
+        let output = quote!(a::b => c);
+        let spacing = output
+            .into_token_iter()
+            .filter_map(|tree| match tree {
+                TokenTree::Punct(punct) => Some((punct.as_char(), punct.spacing())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spacing,
+            [
+                (':', Spacing::Joint),
+                (':', Spacing::Alone),
+                ('=', Spacing::Joint),
+                ('>', Spacing::Alone),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_interpolation() {
+        let name = Ident::new("Foo", Span::call_site());
+        let inner = quote!(x: u8);
+        let output = quote!(struct #name { #inner } #[derive(Debug)]);
+
+        assert_eq!(
+            output.to_string(),
+            "struct Foo { x : u8 } # [derive (Debug)]"
+        );
+
+        let value = 1_u8;
+        let label = "one";
+        let flag = Some(true);
+        let output = quote!(#value #label #flag);
+
+        assert_eq!(output.to_string(), "1u8 \"one\" true");
+
+        let (finite, nan, neg_inf) = (1.5_f32, f32::NAN, f64::NEG_INFINITY);
+        let output = quote!(#finite #nan #neg_inf);
+
+        assert_eq!(
+            output.to_string(),
+            "1.5f32 :: core :: f32 :: NAN :: core :: f64 :: NEG_INFINITY"
+        );
+    }
+
+    #[test]
+    fn test_quote_repetition() {
+        let names = vec![
+            Ident::new("a", Span::call_site()),
+            Ident::new("b", Span::call_site()),
+        ];
+        let types = ["u8", "u16"]
+            .iter()
+            .map(|ty| Ident::new(ty, Span::call_site()));
+
+        let output = quote!(#(#names: #types),*);
+        assert_eq!(output.to_string(), "a : u8 , b : u16");
+
+        let output = quote!(#(let #names = #names;)*);
+        assert_eq!(output.to_string(), "let a = a ; let b = b ;");
+
+        let empty: Vec<Ident> = vec![];
+        let output = quote!(foo(#(#empty),*));
+        assert_eq!(output.to_string(), "foo ()");
+    }
+
+    #[test]
+    fn test_format_ident() {
+        let name = Ident::new("Foo", Span::call_site());
+        let field = Ident::new_raw("type", Span::call_site());
+
+        assert_eq!(
+            format_ident!("{}Builder", name).unwrap().to_string(),
+            "FooBuilder"
+        );
+        assert_eq!(
+            format_ident!("get_{}", field).unwrap().to_string(),
+            "get_type"
+        );
+        assert_eq!(format_ident!("{}", field).unwrap().to_string(), "r#type");
+        assert_eq!(
+            format_ident!("{}_{}", "field", 2_usize, span = Span::call_site())
+                .unwrap()
+                .to_string(),
+            "field_2"
+        );
+        assert_eq!(format_ident!("self").unwrap().to_string(), "self");
+        assert!(format_ident!("{}-{}", name, field).is_err());
+        assert!(format_ident!("{}", 1_u8).is_err());
+        assert!(format_ident!("r#r#foo").is_err());
+        assert!(format_ident!("foo // comment").is_err());
+
+        // `XID_Continue` combining marks are valid, alphabetic characters outside `XID_Start` are
+        // not.
+        assert_eq!(
+            format_ident!("cafe\u{301}").unwrap().to_string(),
+            "cafe\u{301}"
+        );
+        assert!(format_ident!("\u{345}x").is_err());
+        assert!(format_ident!("\u{2e2f}").is_err());
+        assert_eq!(format_ident!("gen").unwrap().to_string(), "r#gen");
+    }
+
+    #[test]
+    fn test_quote_nested_repetition() {
+        let rows = vec![
+            vec![Ident::new("a", Span::call_site())],
+            vec![
+                Ident::new("b", Span::call_site()),
+                Ident::new("c", Span::call_site()),
+            ],
+        ];
+
+        let output = quote!(#([#(#rows)*]),*);
+        assert_eq!(output.to_string(), "[a] , [b c]");
+    }
+}
@@ -1,5 +1,7 @@
 //! Re-exports all public items.
 
+pub use crate::token;
 pub use crate::traits::*;
 pub use crate::ty::*;
 pub use crate::utils::*;
+pub use crate::{format_ident, quote, quote_spanned};
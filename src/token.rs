@@ -0,0 +1,263 @@
+//! Token markers for declarative lookahead.
+//!
+//! Each marker implements [`Peek`], which allows checking the next tokens without consuming
+//! them. A [`Lookahead`] remembers every marker it was asked about, so a parser that tries several
+//! alternatives gets a descriptive error for free:
+//!
+//! ```ignore
+//! let mut lookahead = input.lookahead();
+//! if lookahead.peek(token::AnyIdent) {
+//!     // ...
+//! } else if lookahead.peek(token::Paren) {
+//!     // ...
+//! } else {
+//!     // "Expected identifier or `(`"
+//!     return Err(lookahead.error());
+//! }
+//! ```
+//!
+//! The markers for identifiers, literals, and lifetimes are prefixed with `Any`, so glob imports
+//! of this module do not shadow `proc_macro::Ident` or [`ty::Lifetime`](crate::ty::Lifetime).
+
+use crate::traits::{Peek, TokenIterExt as _, TokenTreeExt as _};
+use crate::ty::TokenIter;
+use crate::utils::spanned_error;
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Matches any identifier, including keywords and raw identifiers.
+///
+/// Check for [`Keyword`]s first when an identifier and a keyword are both valid.
+#[derive(Clone, Copy, Debug)]
+pub struct AnyIdent;
+
+/// Matches any literal, e.g. `42` or `"hello"`.
+#[derive(Clone, Copy, Debug)]
+pub struct AnyLiteral;
+
+/// Matches a lifetime, e.g. `'a`.
+#[derive(Clone, Copy, Debug)]
+pub struct AnyLifetime;
+
+/// Matches an identifier with the given name, e.g. `Keyword("move")`.
+///
+/// Raw identifiers never match, so `r#move` is not the keyword `move`.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyword(pub &'static str);
+
+/// Matches punctuation with the given characters, e.g. `Op("::")`.
+///
+/// See [`TokenIterExt::peek_op`](crate::traits::TokenIterExt::peek_op) for how spacing is
+/// handled.
+#[derive(Clone, Copy, Debug)]
+pub struct Op(pub &'static str);
+
+/// Matches a group delimited by `(...)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Paren;
+
+/// Matches a group delimited by `[...]`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bracket;
+
+/// Matches a group delimited by `{...}`.
+#[derive(Clone, Copy, Debug)]
+pub struct Brace;
+
+/// Lookahead that records the tokens it was asked about.
+///
+/// Created with [`TokenIterExt::lookahead`](crate::traits::TokenIterExt::lookahead).
+#[derive(Clone, Debug)]
+pub struct Lookahead {
+    iter: TokenIter,
+    expected: Vec<String>,
+}
+
+impl Lookahead {
+    /// Create a lookahead at the current position of `iter`.
+    #[must_use]
+    pub fn new(iter: &TokenIter) -> Self {
+        Self {
+            iter: iter.fork(),
+            expected: vec![],
+        }
+    }
+
+    /// Check if the next tokens match `token`.
+    ///
+    /// When they do not, the token is added to the expected set used by [`Lookahead::error`].
+    pub fn peek<T: Peek>(&mut self, token: T) -> bool {
+        if token.peek(&self.iter) {
+            return true;
+        }
+
+        let description = token.description();
+        if !self.expected.contains(&description) {
+            self.expected.push(description);
+        }
+
+        false
+    }
+
+    /// Create an error listing every token that was checked, spanning the next token.
+    ///
+    /// E.g. "Expected identifier or `(`".
+    #[must_use]
+    pub fn error(&self) -> TokenStream {
+        let span = self.iter.peek().cloned().as_span();
+
+        let message = match self.expected.as_slice() {
+            [] => "Unexpected token".to_string(),
+            [expected] => format!("Expected {expected}"),
+            [first, second] => format!("Expected {first} or {second}"),
+            expected => format!("Expected one of {}", expected.join(", ")),
+        };
+
+        spanned_error(message, span)
+    }
+}
+
+impl Peek for AnyIdent {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        matches!(iter.peek(), Some(TokenTree::Ident(_)))
+    }
+
+    fn description(&self) -> String {
+        "identifier".to_string()
+    }
+}
+
+impl Peek for AnyLiteral {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        matches!(iter.peek(), Some(TokenTree::Literal(_)))
+    }
+
+    fn description(&self) -> String {
+        "literal".to_string()
+    }
+}
+
+impl Peek for AnyLifetime {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        iter.fork().try_lifetime().is_ok()
+    }
+
+    fn description(&self) -> String {
+        "lifetime".to_string()
+    }
+}
+
+impl Peek for Keyword {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        iter.peek_keyword(&[self.0]).is_some()
+    }
+
+    fn description(&self) -> String {
+        format!("`{}`", self.0)
+    }
+}
+
+impl Peek for Op {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        iter.peek_op(self.0)
+    }
+
+    fn description(&self) -> String {
+        format!("`{}`", self.0)
+    }
+}
+
+impl Peek for Paren {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        peek_group(iter, Delimiter::Parenthesis)
+    }
+
+    fn description(&self) -> String {
+        "`(`".to_string()
+    }
+}
+
+impl Peek for Bracket {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        peek_group(iter, Delimiter::Bracket)
+    }
+
+    fn description(&self) -> String {
+        "`[`".to_string()
+    }
+}
+
+impl Peek for Brace {
+    fn peek(&self, iter: &TokenIter) -> bool {
+        peek_group(iter, Delimiter::Brace)
+    }
+
+    fn description(&self) -> String {
+        "`{`".to_string()
+    }
+}
+
+fn peek_group(iter: &TokenIter, delimiter: Delimiter) -> bool {
+    matches!(iter.peek(), Some(TokenTree::Group(group)) if group.delimiter() == delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::TokenStreamExt as _;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_peek() {
+        let input = TokenStream::from_str("foo").unwrap().into_token_iter();
+        assert!(input.peek_is(AnyIdent));
+        assert!(input.peek_is(Keyword("foo")));
+        assert!(!input.peek_is(Keyword("bar")));
+        assert!(!input.peek_is(AnyLiteral));
+
+        let input = TokenStream::from_str("r#move").unwrap().into_token_iter();
+        assert!(input.peek_is(AnyIdent));
+        assert!(!input.peek_is(Keyword("move")));
+
+        let input = TokenStream::from_str("'a").unwrap().into_token_iter();
+        assert!(input.peek_is(AnyLifetime));
+        assert!(input.peek_is(Op("'")));
+        assert!(!input.peek_is(AnyIdent));
+
+        let input = TokenStream::from_str(":: 42").unwrap().into_token_iter();
+        assert!(input.peek_is(Op("::")));
+        assert!(!input.peek_is(Op("->")));
+
+        let input = TokenStream::from_str("(a) [b] {c}")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.peek_is(Paren));
+        assert!(!input.peek_is(Bracket));
+        assert!(!input.peek_is(Brace));
+    }
+
+    #[test]
+    fn test_lookahead() {
+        let input = TokenStream::from_str("42").unwrap().into_token_iter();
+        let mut lookahead = input.lookahead();
+        assert!(!lookahead.peek(AnyIdent));
+        assert!(!lookahead.peek(Paren));
+        assert!(lookahead
+            .error()
+            .to_string()
+            .contains("Expected identifier or `(`"));
+        assert!(!lookahead.peek(Keyword("move")));
+        assert!(!lookahead.peek(Op("::")));
+        assert!(!lookahead.peek(AnyIdent));
+        assert!(lookahead
+            .error()
+            .to_string()
+            .contains("Expected one of identifier, `(`, `move`, `::`"));
+        assert!(lookahead.peek(AnyLiteral));
+
+        let input = TokenStream::new().into_token_iter();
+        let mut lookahead = input.lookahead();
+        assert!(lookahead.error().to_string().contains("Unexpected token"));
+        assert!(!lookahead.peek(Brace));
+        assert!(lookahead.error().to_string().contains("Expected `{`"));
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! The primary trait is [`TokenIterExt`], which provides the parsers.
 
-use crate::ty::{Attribute, TokenIter};
+use crate::token::Lookahead;
+use crate::ty::{
+    Attribute, CfgPredicate, Expr, Field, Generics, Item, ItemFn, ItemImpl, ItemTrait, Lifetime,
+    Pat, Punctuated, Signature, TokenIter, Union, Visibility,
+};
 use crate::utils::spanned_error;
-use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Span, TokenStream, TokenTree};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 
 /// An extension trait for [`TokenStream`].
 pub trait TokenStreamExt {
@@ -24,11 +28,27 @@ pub trait TokenIterExt: Iterator<Item = TokenTree> {
     /// `proc_macro` stream.
     fn parse_attributes(&mut self) -> Result<Vec<Attribute>, TokenStream>;
 
+    /// Parse the input iterator into a list of inner attributes.
+    ///
+    /// E.g. `#![doc = "..."]` at the start of a module or function body.
+    ///
+    /// Parsing stops at the first token that does not start an inner attribute, so outer
+    /// attributes following the inner attributes are left in the stream. The returned
+    /// [`Attribute`]s have their [`style`](Attribute::style) set to
+    /// [`AttrStyle::Inner`](crate::ty::AttrStyle::Inner).
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_inner_attributes(&mut self) -> Result<Vec<Attribute>, TokenStream>;
+
     /// Parse the input iterator as a type visibility modifier.
     ///
     /// E.g. `pub` or `pub(super)`.
     ///
     /// This parser currently discards the result, since it doesn't have much use in a derive macro.
+    /// Use [`TokenIterExt::parse_visibility_full`] to keep it.
     ///
     /// # Errors
     ///
@@ -36,6 +56,173 @@ pub trait TokenIterExt: Iterator<Item = TokenTree> {
     /// `proc_macro` stream.
     fn parse_visibility(&mut self) -> Result<(), TokenStream>;
 
+    /// Parse the input iterator as a type visibility modifier, keeping the result.
+    ///
+    /// E.g. `pub` or `pub(super)`.
+    ///
+    /// Returns [`Visibility::Inherited`] when there is no visibility modifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_visibility_full(&mut self) -> Result<Visibility, TokenStream>;
+
+    /// Parse the input iterator as a type, keeping its tokens.
+    ///
+    /// E.g. `std::collections::HashMap<i32, String>`.
+    ///
+    /// The type ends at the first `,`, `;`, `=`, unmatched `>`, `{...}` group, or `where` keyword
+    /// outside of `<...>` nesting. Unlike [`TokenIterExt::parse_path`], the type is not validated.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_type(&mut self) -> Result<TokenStream, TokenStream>;
+
+    /// Collect the tokens of an expression, up to a top-level `,`, `;`, or `=>`.
+    ///
+    /// The separator is not consumed. Commas are allowed inside turbofish arguments like
+    /// `Vec::<u8, u16>::new()` and closure parameters like `|a, b| a + b`. A `<` following an
+    /// operand is treated as a comparison.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_expr_tokens(&mut self) -> Result<TokenStream, TokenStream>;
+
+    /// Parse an expression with [`TokenIterExt::parse_expr_tokens`] and classify it by its
+    /// top-level tokens, e.g. as a literal, path, or call.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_expr(&mut self) -> Result<Expr, TokenStream>;
+
+    /// Parse a pattern, e.g. `(a, b)`, `Foo { x, .. }`, or `ref mut x`.
+    ///
+    /// Supports identifiers, wildcards, rest patterns, literals, references, tuples, slices,
+    /// paths, structs, and tuple structs. Or-patterns and range patterns are not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_pat(&mut self) -> Result<Pat, TokenStream>;
+
+    /// Parse the input iterator as generic parameters.
+    ///
+    /// E.g. `<'a, T: Clone + 'a, const N: usize = 4>`.
+    ///
+    /// Returns empty [`Generics`] when the input does not start with `<`. The where clause is
+    /// parsed separately with [`TokenIterExt::parse_where_clause`], since it is not always adjacent
+    /// to the parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_generics(&mut self) -> Result<Generics, TokenStream>;
+
+    /// Parse the input iterator as a where clause into a list of predicates.
+    ///
+    /// E.g. `where T: Clone, U: Default` returns the predicates `T: Clone` and `U: Default`.
+    ///
+    /// Returns an empty list when the input does not start with `where`. The where clause ends at
+    /// the first `;` or `{...}` group.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_where_clause(&mut self) -> Result<Vec<TokenStream>, TokenStream>;
+
+    /// Parse the input iterator as a list of named fields.
+    ///
+    /// E.g. the contents of `{ pub a: u8, b: String }`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_fields(&mut self) -> Result<Vec<Field>, TokenStream>;
+
+    /// Parse the input iterator as a `union` declaration.
+    ///
+    /// E.g. `#[repr(C)] pub union Foo<T: Copy> { a: u32, b: T }`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_union(&mut self) -> Result<Union, TokenStream>;
+
+    /// Parse the input iterator as a function signature.
+    ///
+    /// E.g. `const unsafe fn foo<T>(&self, value: T) -> Option<T> where T: Copy`.
+    ///
+    /// Parsing stops before the function body or `;`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_signature(&mut self) -> Result<Signature, TokenStream>;
+
+    /// Parse the input iterator as a function item.
+    ///
+    /// E.g. `#[inline] pub async fn foo(a: u8) -> u8 { a }`.
+    ///
+    /// The function body is not parsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_fn(&mut self) -> Result<ItemFn, TokenStream>;
+
+    /// Parse the input iterator as an `impl` block.
+    ///
+    /// E.g. `impl<T> Trait for Foo<T> where T: Clone { ... }`.
+    ///
+    /// Methods are parsed with [`TokenIterExt::parse_signature`], leaving their bodies untouched.
+    /// Associated constants, types, and macro invocations are kept as raw tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_impl(&mut self) -> Result<ItemImpl, TokenStream>;
+
+    /// Parse the input iterator as a trait definition.
+    ///
+    /// E.g. `pub trait Foo<T>: Bar where T: Clone { ... }`.
+    ///
+    /// Method default bodies are kept as raw groups. Associated types and constants are parsed
+    /// with their bounds, types, and defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_trait(&mut self) -> Result<ItemTrait, TokenStream>;
+
+    /// Parse the input iterator as any item.
+    ///
+    /// The item kind is determined by looking past attributes and visibility. Functions, unions,
+    /// `impl` blocks, and traits are fully parsed. All other items are kept as raw tokens along
+    /// with their name, which is useful for reporting unsupported items with
+    /// [`Item::kind`] and [`Item::span`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_item(&mut self) -> Result<Item, TokenStream>;
+
     /// Parse the input iterator as a path into a string/span pair.
     ///
     /// E.g. `std::collections::HashMap<i32, String>`.
@@ -49,6 +236,16 @@ pub trait TokenIterExt: Iterator<Item = TokenTree> {
     /// `proc_macro` stream.
     fn parse_path(&mut self) -> Result<(String, Span), TokenStream>;
 
+    /// Parse the input iterator as a configuration predicate.
+    ///
+    /// E.g. `all(unix, not(feature = "std"))` from `#[cfg(all(unix, not(feature = "std")))]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_cfg_predicate(&mut self) -> Result<CfgPredicate, TokenStream>;
+
     /// Parse the input as a group, expecting the given delimiter.
     ///
     /// Returns the group's inner [`TokenStream`] as a [`TokenIter`] when successful.
@@ -84,6 +281,97 @@ pub trait TokenIterExt: Iterator<Item = TokenTree> {
     /// `proc_macro` stream.
     fn expect_punct(&mut self, expect: char) -> Result<(), TokenStream>;
 
+    /// Parse a value of any type implementing [`Parse`], e.g. `iter.parse::<Ident>()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse<T: Parse>(&mut self) -> Result<T, TokenStream>;
+
+    /// Apply `parser`, then check that no input remains.
+    ///
+    /// ```ignore
+    /// let mut args = input.expect_group(Delimiter::Parenthesis)?;
+    /// let name = args.parse_all(TokenIterExt::try_ident)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails or if any tokens remain after parsing. The error
+    /// should be inserted into the `proc_macro` stream.
+    fn parse_all<T, F>(&mut self, parser: F) -> Result<T, TokenStream>
+    where
+        F: FnOnce(&mut TokenIter) -> Result<T, TokenStream>;
+
+    /// Expect the end of input, e.g. after parsing the contents of a group or attribute.
+    ///
+    /// This method does not consume the next item from the stream when an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error spanning the first remaining token. The error should be inserted
+    /// into the `proc_macro` stream.
+    fn expect_end(&mut self) -> Result<(), TokenStream>;
+
+    /// Check if the next tokens match `token`, e.g. `iter.peek_is(token::Op("::"))`.
+    ///
+    /// No tokens are consumed. See the [`token`](crate::token) module for available markers.
+    fn peek_is<T: Peek>(&self, token: T) -> bool;
+
+    /// Create a [`Lookahead`] at the current position.
+    ///
+    /// The lookahead records every token it is asked about, and reports them all in its error.
+    fn lookahead(&self) -> Lookahead;
+
+    /// Check if the next item is an identifier matching one of `keywords`, and return the match.
+    ///
+    /// Raw identifiers never match, so `r#async` is not the keyword `async`. No tokens are
+    /// consumed.
+    fn peek_keyword<'k>(&self, keywords: &[&'k str]) -> Option<&'k str>;
+
+    /// Parse the input as an identifier, expecting it to match one of `keywords`.
+    ///
+    /// Raw identifiers never match, so `r#async` is not the keyword `async`. This method should
+    /// always consume the next item from the stream, even when an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn expect_one_of(&mut self, keywords: &[&str]) -> Result<Ident, TokenStream>;
+
+    /// Parse the input as a lifetime, expecting it to match the given name.
+    ///
+    /// The name may be given with or without the leading `'`. This method should always consume
+    /// the next item from the stream, even when an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn expect_lifetime(&mut self, expect: &str) -> Result<(), TokenStream>;
+
+    /// Check if the next tokens form the multi-character operator `op`, like `::` or `->`.
+    ///
+    /// Every character except the last must have [`Spacing::Joint`]. No tokens are consumed.
+    ///
+    /// [`Spacing::Joint`]: proc_macro::Spacing::Joint
+    fn peek_op(&self, op: &str) -> bool;
+
+    /// Parse the input as a multi-character operator, like `::`, `->`, or `..=`, and return its
+    /// span.
+    ///
+    /// The span is that of the first character, because spans cannot be joined on stable Rust.
+    /// This method should always consume at least the next item from the stream, even when an
+    /// error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn expect_op(&mut self, op: &str) -> Result<Span, TokenStream>;
+
     /// Try to parse the input as a group.
     ///
     /// This method should not consume the next item from the stream when an error is returned.
@@ -131,6 +419,112 @@ pub trait TokenIterExt: Iterator<Item = TokenTree> {
     /// Returns a compiler error if parsing fails. The error should be inserted into the
     /// `proc_macro` stream.
     fn try_punct(&mut self) -> Result<Punct, TokenStream>;
+
+    /// Try to parse the input as a lifetime, e.g. `'a`.
+    ///
+    /// This method should not consume the next item from the stream when an error is returned.
+    /// An implementation which always consumes will make it difficult for parsers to try
+    /// alternative matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn try_lifetime(&mut self) -> Result<Lifetime, TokenStream>;
+
+    /// Parse the input iterator as a list of items separated by `sep`, until the end of input.
+    ///
+    /// Each item is parsed by calling `parser`. Empty lists and a trailing separator are
+    /// accepted. This is typically called on the contents of a group, e.g. from
+    /// [`TokenIterExt::expect_group`].
+    ///
+    /// ```ignore
+    /// let args = input.expect_group(Delimiter::Parenthesis)?;
+    /// let names = args.parse_punctuated(',', TokenIterExt::try_ident)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse_punctuated<T, F>(
+        &mut self,
+        sep: char,
+        parser: F,
+    ) -> Result<Punctuated<T>, TokenStream>
+    where
+        F: FnMut(&mut TokenIter) -> Result<T, TokenStream>;
+}
+
+/// A trait for types that can be converted to tokens.
+///
+/// This is used for interpolating variables in [`quote!`].
+///
+/// [`quote!`]: crate::quote
+pub trait ToTokens {
+    /// Append the tokens representing this value to the given [`TokenStream`].
+    fn to_tokens(&self, tokens: &mut TokenStream);
+
+    /// Convert this value into a new [`TokenStream`].
+    fn to_token_stream(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        self.to_tokens(&mut tokens);
+
+        tokens
+    }
+}
+
+/// A trait for values that can be formatted into an identifier with [`format_ident!`].
+///
+/// Raw identifiers are formatted without their `r#` prefix, so `r#type` can be used to create
+/// `get_type`.
+///
+/// [`format_ident!`]: crate::format_ident
+pub trait IdentFragment {
+    /// Format this value as part of an identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the formatter fails.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+
+    /// The span to use for the identifier, if this value has one.
+    fn span(&self) -> Option<Span> {
+        None
+    }
+}
+
+/// A type which can be parsed from a [`TokenIter`].
+///
+/// Implement this for custom grammar types to compose them with [`TokenIterExt::parse`],
+/// [`parse_stream`](crate::utils::parse_stream), and the `Option<T>` and `Vec<T>` combinators.
+///
+/// Implementations should not consume any input when the first token does not match, so that
+/// `Option<T>` can return `None` instead of the error.
+///
+/// To parse speculatively, parse from a [`TokenIter::fork`] and commit to it with
+/// [`TokenIter::advance_to`]. Note that `advance_to` panics when given a fork of a different
+/// iterator, such as the iterator returned by [`TokenIterExt::expect_group`].
+pub trait Parse: Sized {
+    /// Parse a value from the input iterator.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream>;
+}
+
+/// A token which can be recognized by looking ahead without consuming input.
+///
+/// This is implemented by the markers in the [`token`](crate::token) module. Markers are passed
+/// by value, so they must be `Copy`.
+pub trait Peek: Copy {
+    /// Returns `true` if the next tokens in `iter` match this token.
+    fn peek(&self, iter: &TokenIter) -> bool;
+
+    /// A description of the token for error messages, e.g. ``"`::`"`` or `"identifier"`.
+    fn description(&self) -> String;
 }
 
 /// An extension trait for [`TokenTree`].
@@ -156,14 +550,281 @@ pub trait LiteralExt {
     /// Returns a compiler error if parsing fails. The error should be inserted into the
     /// `proc_macro` stream.
     fn as_string(&self) -> Result<String, TokenStream>;
+
+    /// Parse a literal into an integer.
+    ///
+    /// Supports decimal, hexadecimal (`0x`), octal (`0o`), and binary (`0b`) literals with
+    /// optional `_` separators and integer type suffixes.
+    ///
+    /// # Errors
+    ///
+    /// Returns a compiler error if parsing fails. The error should be inserted into the
+    /// `proc_macro` stream.
+    fn as_int(&self) -> Result<i128, TokenStream>;
 }
 
 impl TokenStreamExt for TokenStream {
     fn into_token_iter(self) -> TokenIter {
-        self.into_iter().peekable()
+        TokenIter::new(self)
+    }
+}
+
+impl<T: ToTokens + ?Sized> ToTokens for &T {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        (**self).to_tokens(tokens);
+    }
+}
+
+impl ToTokens for TokenStream {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.clone());
+    }
+}
+
+impl ToTokens for TokenTree {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend([self.clone()]);
+    }
+}
+
+impl ToTokens for Group {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend([TokenTree::Group(self.clone())]);
+    }
+}
+
+impl ToTokens for Ident {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend([TokenTree::Ident(self.clone())]);
+    }
+}
+
+impl ToTokens for Literal {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend([TokenTree::Literal(self.clone())]);
+    }
+}
+
+impl ToTokens for Punct {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend([TokenTree::Punct(self.clone())]);
+    }
+}
+
+impl<T: ToTokens + ?Sized> ToTokens for Box<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        (**self).to_tokens(tokens);
+    }
+}
+
+impl<T: ToTokens> ToTokens for Option<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(inner) = self {
+            inner.to_tokens(tokens);
+        }
+    }
+}
+
+impl<T: ToTokens> ToTokens for [T] {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for item in self {
+            item.to_tokens(tokens);
+        }
+    }
+}
+
+impl<T: ToTokens> ToTokens for Vec<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.as_slice().to_tokens(tokens);
+    }
+}
+
+impl ToTokens for str {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        Literal::string(self).to_tokens(tokens);
+    }
+}
+
+impl ToTokens for String {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.as_str().to_tokens(tokens);
+    }
+}
+
+impl ToTokens for char {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        Literal::character(*self).to_tokens(tokens);
+    }
+}
+
+impl ToTokens for bool {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ident = if *self { "true" } else { "false" };
+        Ident::new(ident, Span::call_site()).to_tokens(tokens);
+    }
+}
+
+macro_rules! impl_to_tokens_for_primitive {
+    ($($ty:ty => $ctor:ident),* $(,)?) => {
+        $(
+            impl ToTokens for $ty {
+                fn to_tokens(&self, tokens: &mut TokenStream) {
+                    Literal::$ctor(*self).to_tokens(tokens);
+                }
+            }
+        )*
+    };
+}
+
+impl_to_tokens_for_primitive!(
+    i8 => i8_suffixed,
+    i16 => i16_suffixed,
+    i32 => i32_suffixed,
+    i64 => i64_suffixed,
+    i128 => i128_suffixed,
+    isize => isize_suffixed,
+    u8 => u8_suffixed,
+    u16 => u16_suffixed,
+    u32 => u32_suffixed,
+    u64 => u64_suffixed,
+    u128 => u128_suffixed,
+    usize => usize_suffixed,
+);
+
+macro_rules! impl_to_tokens_for_float {
+    ($($ty:ident => $ctor:ident),* $(,)?) => {
+        $(
+            /// Non-finite values cannot be written as literals, so they are emitted as paths to
+            /// the associated constants, e.g. `::core::f32::NAN`.
+            impl ToTokens for $ty {
+                fn to_tokens(&self, tokens: &mut TokenStream) {
+                    let name = if self.is_nan() {
+                        "NAN"
+                    } else if *self == $ty::INFINITY {
+                        "INFINITY"
+                    } else if *self == $ty::NEG_INFINITY {
+                        "NEG_INFINITY"
+                    } else {
+                        Literal::$ctor(*self).to_tokens(tokens);
+                        return;
+                    };
+
+                    for segment in ["core", stringify!($ty), name] {
+                        tokens.extend([
+                            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                            TokenTree::Ident(Ident::new(segment, Span::call_site())),
+                        ]);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_to_tokens_for_float!(
+    f32 => f32_suffixed,
+    f64 => f64_suffixed,
+);
+
+impl Parse for Group {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.try_group()
     }
 }
 
+impl Parse for Ident {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.try_ident()
+    }
+}
+
+impl Parse for Literal {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.try_lit()
+    }
+}
+
+impl Parse for Punct {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.try_punct()
+    }
+}
+
+impl Parse for TokenTree {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.next()
+            .ok_or_else(|| spanned_error("Unexpected end of input", Span::call_site()))
+    }
+}
+
+/// Parses `T` if possible, otherwise returns `None` without consuming any input.
+///
+/// Errors are only discarded when `T` fails without consuming any input. Once `T` has consumed a
+/// token, the input is committed to `T` and its error is returned.
+impl<T: Parse> Parse for Option<T> {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        let mut fork = iter.fork();
+        match T::parse(&mut fork) {
+            Ok(value) => {
+                iter.advance_to(&fork);
+                Ok(Some(value))
+            }
+            Err(_) if fork.offset() == iter.offset() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Parses `T` repeatedly until the end of input.
+impl<T: Parse> Parse for Vec<T> {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        let mut values = vec![];
+        while iter.peek().is_some() {
+            values.push(T::parse(iter)?);
+        }
+
+        Ok(values)
+    }
+}
+
+impl<T: IdentFragment + ?Sized> IdentFragment for &T {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        IdentFragment::fmt(*self, f)
+    }
+
+    fn span(&self) -> Option<Span> {
+        IdentFragment::span(*self)
+    }
+}
+
+impl IdentFragment for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ident = self.to_string();
+        f.write_str(ident.strip_prefix("r#").unwrap_or(&ident))
+    }
+
+    fn span(&self) -> Option<Span> {
+        Some(self.span())
+    }
+}
+
+macro_rules! impl_ident_fragment_for_display {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl IdentFragment for $ty {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Display::fmt(self, f)
+                }
+            }
+        )*
+    };
+}
+
+impl_ident_fragment_for_display!(
+    str, String, char, bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize,
+);
+
 impl TokenTreeExt for Option<TokenTree> {
     fn as_span(&self) -> Span {
         match self {
@@ -205,4 +866,31 @@ impl LiteralExt for Literal {
             .replace(r"\'", "'")
             .replace(r"\\", r"\"))
     }
+
+    fn as_int(&self) -> Result<i128, TokenStream> {
+        const SUFFIXES: [&str; 12] = [
+            "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        ];
+
+        let string = self.to_string().replace('_', "");
+        let (digits, radix) = match string.get(..2) {
+            Some("0x") => (&string[2..], 16),
+            Some("0o") => (&string[2..], 8),
+            Some("0b") => (&string[2..], 2),
+            _ => (string.as_str(), 10),
+        };
+        let digits = SUFFIXES
+            .iter()
+            .find_map(|suffix| digits.strip_suffix(suffix))
+            .unwrap_or(digits);
+
+        match u128::from_str_radix(digits, radix).map(i128::try_from) {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(_)) => Err(spanned_error("Integer literal out of range", self.span())),
+            Err(err) if *err.kind() == std::num::IntErrorKind::PosOverflow => {
+                Err(spanned_error("Integer literal out of range", self.span()))
+            }
+            Err(_) => Err(spanned_error("Expected integer literal", self.span())),
+        }
+    }
 }
@@ -1,16 +1,118 @@
 //! High-level types from the parser.
 
-use crate::traits::{TokenIterExt, TokenStreamExt as _, TokenTreeExt as _};
+use crate::macros::push_punct;
+use crate::token::{self, Lookahead};
+use crate::traits::{Parse, Peek, ToTokens, TokenIterExt, TokenStreamExt as _, TokenTreeExt as _};
 use crate::utils::spanned_error;
 use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
-use std::iter::Peekable;
+use std::rc::Rc;
 
-/// A type alias for the primary [`TokenTree`] iterator.
+/// The primary [`TokenTree`] iterator.
 ///
-/// It is [`Peekable`] to allow look-ahead of single items.
-pub type TokenIter = Peekable<proc_macro::token_stream::IntoIter>;
+/// This is a cursor into a shared buffer of tokens, so cloning it is cheap. Clones made with
+/// [`TokenIter::fork`] can parse speculatively, and the original cursor can then be moved forward
+/// with [`TokenIter::advance_to`] or left untouched to backtrack.
+#[derive(Clone, Debug)]
+pub struct TokenIter {
+    tokens: Rc<[TokenTree]>,
+    pos: usize,
+}
+
+impl TokenIter {
+    /// Create an iterator over the token trees in `stream`.
+    #[must_use]
+    pub fn new(stream: TokenStream) -> Self {
+        Self {
+            tokens: stream.into_iter().collect(),
+            pos: 0,
+        }
+    }
+
+    /// Returns a reference to the next token tree without advancing the iterator.
+    #[must_use]
+    pub fn peek(&self) -> Option<&TokenTree> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Returns a reference to the token tree `n` positions ahead without advancing the iterator.
+    ///
+    /// `peek_n(0)` is equivalent to [`TokenIter::peek`].
+    #[must_use]
+    pub fn peek_n(&self, n: usize) -> Option<&TokenTree> {
+        self.tokens.get(self.pos + n)
+    }
+
+    /// Consume and return the next token tree if `func` returns `true` for it.
+    pub fn next_if(&mut self, func: impl FnOnce(&TokenTree) -> bool) -> Option<TokenTree> {
+        match self.peek() {
+            Some(tree) if func(tree) => self.next(),
+            _ => None,
+        }
+    }
+
+    /// Create a copy of this cursor for speculative parsing.
+    ///
+    /// Parsing from the fork does not advance this cursor.
+    #[must_use]
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    /// Move this cursor to the position of `fork`, committing to what was parsed from it.
+    ///
+    /// Forks share their token buffer with the cursor they came from, so a fork of a nested
+    /// group's iterator cannot be used to advance the outer cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fork` was not created from this cursor with [`TokenIter::fork`], or if it is
+    /// behind this cursor.
+    pub fn advance_to(&mut self, fork: &Self) {
+        assert!(
+            Rc::ptr_eq(&self.tokens, &fork.tokens) && fork.pos >= self.pos,
+            "`advance_to` requires a fork of this cursor",
+        );
+        self.pos = fork.pos;
+    }
+
+    /// The number of token trees consumed so far.
+    ///
+    /// Comparing offsets tells how far a fork has advanced. This is not named `position` because
+    /// [`Iterator::position`] would take precedence when called through `&mut TokenIter`.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+}
+
+impl Iterator for TokenIter {
+    type Item = TokenTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.tokens.get(self.pos).cloned();
+        if tree.is_some() {
+            self.pos += 1;
+        }
+
+        tree
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.tokens.len() - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for TokenIter {}
+
+impl From<TokenStream> for TokenIter {
+    fn from(stream: TokenStream) -> Self {
+        Self::new(stream)
+    }
+}
 
 /// A type representing `#[attributes]`.
+#[derive(Clone)]
 pub struct Attribute {
     /// The attribute name.
     ///
@@ -19,218 +121,4642 @@ pub struct Attribute {
 
     /// The inner [`TokenTree`] iterator.
     pub tree: TokenIter,
+
+    /// Whether this is an outer `#[...]` or inner `#![...]` attribute.
+    pub style: AttrStyle,
 }
 
-impl TokenIterExt for TokenIter {
-    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, TokenStream> {
-        let mut attrs = vec![];
+/// The placement of an [`Attribute`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AttrStyle {
+    /// An outer attribute, e.g. `#[derive(Debug)]`, applying to the item that follows.
+    Outer,
 
-        loop {
-            match self.peek() {
-                Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => self.next(),
-                _ => break,
-            };
+    /// An inner attribute, e.g. `#![allow(dead_code)]`, applying to the enclosing item.
+    Inner,
+}
+
+/// A type path as parsed by [`TokenIterExt::parse_path`], e.g. `Vec<u8>`.
+#[derive(Clone, Debug)]
+pub struct Path {
+    /// The path as a string, with whitespace only between adjacent words.
+    pub path: String,
+
+    /// The span of the first token.
+    pub span: Span,
+}
+
+/// A type as parsed by [`TokenIterExt::parse_type`], e.g. `HashMap<String, u8>`.
+#[derive(Clone, Debug)]
+pub struct Type {
+    /// The type tokens.
+    pub tokens: TokenStream,
+}
+
+/// An expression kept as tokens, with a shallow classification.
+#[derive(Clone, Debug)]
+pub struct Expr {
+    /// The kind of expression.
+    pub kind: ExprKind,
+
+    /// The expression tokens.
+    pub tokens: TokenStream,
+}
+
+/// The kind of an [`Expr`], determined only from its top-level tokens.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExprKind {
+    /// A literal, e.g. `42`, `"foo"`, or `true`.
+    Lit,
+
+    /// A path, e.g. `x`, `Foo::BAR`, or `Vec::<u8>::new`.
+    Path,
+
+    /// A function call, e.g. `foo(1, 2)`.
+    Call,
+
+    /// A method call, e.g. `x.foo(1)`.
+    MethodCall,
+
+    /// A unary operation, e.g. `-x`, `!x`, `*x`, or `&x`.
+    Unary,
+
+    /// A binary operation, e.g. `a + b * 2` or `a..b`.
+    Binary,
+
+    /// A block, e.g. `{ a + b }`.
+    Block,
+
+    /// A macro invocation, e.g. `vec![1, 2]`.
+    Macro,
+
+    /// Any other expression, e.g. a closure, cast, field access, tuple, `if`, or `match`.
+    Other,
+}
+
+/// A type representing visibility modifiers.
+#[derive(Clone, Debug)]
+pub enum Visibility {
+    /// No visibility modifier.
+    Inherited,
+
+    /// `pub`
+    Public(Ident),
+
+    /// `pub` with a restriction, e.g. `pub(crate)` or `pub(in some::path)`.
+    Restricted(Ident, Group),
+}
+
+/// A type representing generic parameters and the where clause.
+#[derive(Clone, Debug, Default)]
+pub struct Generics {
+    /// The generic parameters.
+    pub params: Vec<GenericParam>,
+
+    /// Predicates in the where clause, e.g. `T: Clone`.
+    pub where_clause: Vec<TokenStream>,
+
+    /// The spans of the `<` and `>` around the parameters, or `None` when there are none.
+    pub brackets: Option<(Span, Span)>,
+}
+
+/// A single generic parameter.
+#[derive(Clone, Debug)]
+pub struct GenericParam {
+    /// Attributes on the parameter.
+    pub attrs: Vec<Attribute>,
+
+    /// The kind of parameter.
+    pub kind: GenericParamKind,
+
+    /// The parameter name.
+    ///
+    /// This would be `a` for the lifetime `'a`.
+    pub name: Ident,
+
+    /// Lifetime or trait bounds, e.g. `Clone + 'a`. Empty when there are no bounds.
+    pub bounds: TokenStream,
+
+    /// The default value, e.g. `String` in `T = String`.
+    pub default: Option<TokenStream>,
+
+    /// The span of the `:` before the bounds or const parameter type.
+    pub colon: Option<Span>,
+
+    /// The span of the `=` before the default value.
+    pub eq: Option<Span>,
+
+    /// The span of the `,` following the parameter.
+    pub comma: Option<Span>,
+}
+
+/// The kind of a [`GenericParam`].
+#[derive(Clone, Debug)]
+pub enum GenericParamKind {
+    /// A lifetime parameter, e.g. `'a`.
+    Lifetime,
+
+    /// A type parameter, e.g. `T`.
+    Type,
+
+    /// A const parameter with its type, e.g. `const N: usize`.
+    Const(TokenStream),
+}
+
+/// A lifetime, e.g. `'a` or `'static`.
+///
+/// Lifetimes are tokenized as a [`Spacing::Joint`] `'` followed by an identifier.
+#[derive(Clone, Debug)]
+pub struct Lifetime {
+    /// The span of the `'`.
+    pub apostrophe: Span,
+
+    /// The lifetime name without the `'`.
+    ///
+    /// This would be `a` for the lifetime `'a`.
+    pub ident: Ident,
+}
+
+impl Lifetime {
+    /// Create a lifetime from its name, with or without the leading `'`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is not a valid identifier.
+    #[must_use]
+    pub fn new(name: &str, span: Span) -> Self {
+        Self {
+            apostrophe: span,
+            ident: Ident::new(name.trim_start_matches('\''), span),
+        }
+    }
+}
+
+impl PartialEq for Lifetime {
+    fn eq(&self, other: &Self) -> bool {
+        self.ident.to_string() == other.ident.to_string()
+    }
+}
+
+impl Eq for Lifetime {}
+
+impl std::fmt::Display for Lifetime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}", self.ident)
+    }
+}
+
+/// A multi-character operator for code generation, like `::`, `->`, or `..=`.
+///
+/// All characters are emitted with [`Spacing::Joint`] except the last, so the compiler sees a
+/// single operator.
+#[derive(Clone, Copy, Debug)]
+pub struct PunctSeq {
+    op: &'static str,
+    span: Span,
+}
+
+impl PunctSeq {
+    /// Create an operator with the call site span.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is empty or contains a character that is not valid punctuation.
+    #[must_use]
+    pub fn new(op: &'static str) -> Self {
+        Self::new_spanned(op, Span::call_site())
+    }
+
+    /// Create an operator with the given span.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is empty or contains a character that is not valid punctuation.
+    #[must_use]
+    pub fn new_spanned(op: &'static str, span: Span) -> Self {
+        assert!(
+            !op.is_empty() && op.chars().all(|ch| "=<>!~+-*/%^&|@.,;:#$?'".contains(ch)),
+            "Invalid operator `{op}`",
+        );
+
+        Self { op, span }
+    }
+
+    /// The operator characters.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        self.op
+    }
+
+    /// The span given to each character.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// A list of items separated by punctuation, parsed by [`TokenIterExt::parse_punctuated`].
+#[derive(Clone, Debug)]
+pub struct Punctuated<T> {
+    /// The parsed items.
+    pub items: Vec<T>,
+
+    /// Spans of the separators following each item. A trailing separator is included.
+    pub separators: Vec<Span>,
+}
+
+impl<T> Punctuated<T> {
+    /// Returns `true` when there are no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The number of items.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` when the last item is followed by a separator.
+    #[must_use]
+    pub fn has_trailing(&self) -> bool {
+        !self.items.is_empty() && self.separators.len() == self.items.len()
+    }
+}
+
+impl<T> IntoIterator for Punctuated<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+/// A type representing a named field.
+#[derive(Clone, Debug)]
+pub struct Field {
+    /// Attributes on the field.
+    pub attrs: Vec<Attribute>,
+
+    /// The field visibility.
+    pub vis: Visibility,
+
+    /// The field name.
+    pub name: Ident,
+
+    /// The span of the `:` between the name and type.
+    pub colon: Span,
+
+    /// The field type.
+    pub ty: TokenStream,
+}
+
+/// A type representing a `union` declaration.
+#[derive(Clone, Debug)]
+pub struct Union {
+    /// Attributes on the union.
+    pub attrs: Vec<Attribute>,
+
+    /// The union visibility.
+    pub vis: Visibility,
+
+    /// The union name.
+    pub name: Ident,
+
+    /// Generic parameters and the where clause.
+    pub generics: Generics,
+
+    /// The union fields.
+    pub fields: Vec<Field>,
+
+    /// The span of the braces around the fields.
+    pub span: Span,
+}
+
+impl Generics {
+    /// Returns `true` if there are no generic parameters and no where clause.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty() && self.where_clause.is_empty()
+    }
+
+    /// The parameters for an `impl` block, e.g. `<'a, T: Clone, const N: usize>`.
+    ///
+    /// Attributes and defaults are removed. Returns an empty stream when there are no parameters.
+    #[must_use]
+    pub fn impl_generics(&self) -> TokenStream {
+        self.params_to_tokens(|param, tokens| {
+            param.name_to_tokens(tokens);
+            param.bounds_to_tokens(tokens);
+        })
+    }
+
+    /// The lifetime parameters, in declaration order.
+    pub fn lifetimes(&self) -> impl Iterator<Item = Lifetime> + '_ {
+        self.params.iter().filter_map(GenericParam::lifetime)
+    }
+
+    /// The arguments for naming the type, e.g. `<'a, T, N>`.
+    ///
+    /// Returns an empty stream when there are no parameters.
+    #[must_use]
+    pub fn type_generics(&self) -> TokenStream {
+        self.params_to_tokens(|param, tokens| {
+            if let GenericParamKind::Lifetime = param.kind {
+                param.name_to_tokens(tokens);
+            } else {
+                param.name.to_tokens(tokens);
+            }
+        })
+    }
+
+    /// The where clause, e.g. `where T: Clone`.
+    ///
+    /// Returns an empty stream when there are no predicates.
+    #[must_use]
+    pub fn where_clause(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        if let Some(first) = self.where_clause.first() {
+            Ident::new("where", first_span(first)).to_tokens(&mut tokens);
+            for predicate in &self.where_clause {
+                predicate.to_tokens(&mut tokens);
+                tokens.extend([spanned_punct(',', last_span(predicate))]);
+            }
+        }
+
+        tokens
+    }
+
+    fn params_to_tokens<F>(&self, f: F) -> TokenStream
+    where
+        F: Fn(&GenericParam, &mut TokenStream),
+    {
+        let mut tokens = TokenStream::new();
+        if !self.params.is_empty() {
+            let (lt, gt) = self
+                .brackets
+                .unwrap_or((Span::call_site(), Span::call_site()));
+
+            tokens.extend([spanned_punct('<', lt)]);
+            for param in &self.params {
+                f(param, &mut tokens);
+                tokens.extend([spanned_punct(',', param.comma.unwrap_or(gt))]);
+            }
+            tokens.extend([spanned_punct('>', gt)]);
+        }
+
+        tokens
+    }
+}
+
+impl GenericParam {
+    /// The parameter as a [`Lifetime`], or `None` if it is not a lifetime parameter.
+    #[must_use]
+    pub fn lifetime(&self) -> Option<Lifetime> {
+        match self.kind {
+            GenericParamKind::Lifetime => Some(Lifetime {
+                apostrophe: self.name.span(),
+                ident: self.name.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The lifetimes named in the bounds, e.g. `'a` and `'b` in `T: Trait<'a> + 'b`.
+    #[must_use]
+    pub fn bound_lifetimes(&self) -> Vec<Lifetime> {
+        let mut lifetimes = vec![];
+        let mut stack = vec![self.bounds.clone().into_token_iter()];
+        while let Some(iter) = stack.last_mut() {
+            if let Ok(lifetime) = iter.try_lifetime() {
+                lifetimes.push(lifetime);
+                continue;
+            }
+
+            match iter.next() {
+                Some(TokenTree::Group(group)) => stack.push(group.stream().into_token_iter()),
+                Some(_) => (),
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        lifetimes
+    }
+
+    /// Emit the bounds or const parameter type with the leading `:`, if any.
+    fn bounds_to_tokens(&self, tokens: &mut TokenStream) {
+        let colon = self.colon.unwrap_or_else(|| self.name.span());
+        if let GenericParamKind::Const(ty) = &self.kind {
+            tokens.extend([spanned_punct(':', colon)]);
+            ty.to_tokens(tokens);
+        } else if !self.bounds.is_empty() {
+            tokens.extend([spanned_punct(':', colon)]);
+            self.bounds.to_tokens(tokens);
+        }
+    }
+
+    /// Emit the name with the `'` for lifetimes, or `const` for const parameters.
+    fn name_to_tokens(&self, tokens: &mut TokenStream) {
+        match &self.kind {
+            GenericParamKind::Lifetime => return self.lifetime().to_tokens(tokens),
+            GenericParamKind::Type => (),
+            GenericParamKind::Const(_) => {
+                Ident::new("const", self.name.span()).to_tokens(tokens);
+            }
+        }
+        self.name.to_tokens(tokens);
+    }
+}
+
+/// A type representing a function item.
+#[derive(Clone, Debug)]
+pub struct ItemFn {
+    /// Attributes on the function.
+    pub attrs: Vec<Attribute>,
+
+    /// The function visibility.
+    pub vis: Visibility,
+
+    /// The `default` qualifier for specializable methods in an `impl` block.
+    pub defaultness: Option<Ident>,
+
+    /// The function signature.
+    pub sig: Signature,
+
+    /// The function body, as an untouched `{...}` group.
+    pub body: Group,
+}
+
+/// A type representing a function signature.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    /// The `const` qualifier.
+    pub constness: Option<Ident>,
+
+    /// The `async` qualifier.
+    pub asyncness: Option<Ident>,
+
+    /// The `unsafe` qualifier.
+    pub unsafety: Option<Ident>,
+
+    /// The `extern` qualifier with its optional ABI string, e.g. `extern "C"`.
+    pub abi: Option<(Ident, Option<Literal>)>,
+
+    /// The `fn` keyword.
+    pub fn_token: Ident,
+
+    /// The function name.
+    pub name: Ident,
+
+    /// Generic parameters and the where clause.
+    pub generics: Generics,
+
+    /// The span of the parentheses around the arguments.
+    pub paren: Span,
+
+    /// The function arguments.
+    pub inputs: Punctuated<FnArg>,
+
+    /// The span of the `->` and the return type. This is `None` for the default `()` return type.
+    pub output: Option<(Span, TokenStream)>,
+}
+
+/// A function argument.
+#[derive(Clone, Debug)]
+pub enum FnArg {
+    /// The `self` argument for methods.
+    Receiver(Receiver),
+
+    /// A typed argument, e.g. `value: u8`.
+    Typed(PatType),
+}
+
+/// A type representing the `self` argument of a method.
+///
+/// E.g. `self`, `&'a mut self`, or `self: Box<Self>`.
+#[derive(Clone, Debug)]
+pub struct Receiver {
+    /// Attributes on the argument.
+    pub attrs: Vec<Attribute>,
+
+    /// The `&` and optional lifetime for references.
+    pub reference: Option<(Punct, Option<Lifetime>)>,
+
+    /// The `mut` keyword.
+    pub mutability: Option<Ident>,
+
+    /// The `self` keyword.
+    pub self_token: Ident,
+
+    /// The span of the `:` before an explicit type.
+    pub colon: Option<Span>,
+
+    /// The explicit type, e.g. `Box<Self>` in `self: Box<Self>`.
+    pub ty: Option<TokenStream>,
+}
+
+/// A type representing a typed argument, e.g. `(a, b): (u8, u8)`.
+#[derive(Clone, Debug)]
+pub struct PatType {
+    /// Attributes on the argument.
+    pub attrs: Vec<Attribute>,
+
+    /// The argument pattern.
+    pub pat: Pat,
+
+    /// The span of the `:` between the pattern and type.
+    pub colon: Span,
+
+    /// The argument type.
+    pub ty: TokenStream,
+}
+
+/// A pattern, e.g. in a function argument or `let` binding.
+#[derive(Clone, Debug)]
+pub enum Pat {
+    /// An identifier binding, e.g. `x`, `ref mut x`, or `x @ Some(_)`.
+    Ident(PatIdent),
+
+    /// A literal with an optional `-`, e.g. `42` or `-1`.
+    Lit(Option<Punct>, Literal),
+
+    /// A path to a constant or unit variant, e.g. `Foo::Bar` or `true`.
+    ///
+    /// A single identifier like `None` is parsed as [`Pat::Ident`], because it cannot be told
+    /// apart from a binding without name resolution.
+    Path(TokenStream),
+
+    /// A reference, e.g. `&x` or `&mut (a, b)`.
+    Reference(PatReference),
+
+    /// The rest pattern `..`, with its span.
+    Rest(Span),
+
+    /// A slice, e.g. `[first, .., last]`, with the span of the brackets.
+    Slice(Span, Punctuated<Pat>),
+
+    /// A struct, e.g. `Foo { x, y: 0, .. }`.
+    Struct(PatStruct),
+
+    /// A tuple, e.g. `(a, b)` or `(a,)`, with the span of the parentheses.
+    ///
+    /// A parenthesized pattern like `(a)` is a tuple without a trailing separator.
+    Tuple(Span, Punctuated<Pat>),
+
+    /// A tuple struct or tuple variant, e.g. `Some(x)` or `Point(x, y)`.
+    TupleStruct(PatTupleStruct),
+
+    /// The wildcard pattern `_`.
+    Wild(Ident),
+}
+
+/// An identifier binding, e.g. `ref mut x`.
+#[derive(Clone, Debug)]
+pub struct PatIdent {
+    /// The `ref` keyword.
+    pub by_ref: Option<Ident>,
+
+    /// The `mut` keyword.
+    pub mutability: Option<Ident>,
+
+    /// The bound identifier.
+    pub ident: Ident,
+
+    /// The span of the `@` and the pattern following it, e.g. `Some(_)` in `x @ Some(_)`.
+    pub subpat: Option<(Span, Box<Pat>)>,
+}
+
+/// A reference pattern, e.g. `&mut x`.
+#[derive(Clone, Debug)]
+pub struct PatReference {
+    /// The `&` token.
+    pub and: Punct,
+
+    /// The `mut` keyword.
+    pub mutability: Option<Ident>,
+
+    /// The referenced pattern.
+    pub pat: Box<Pat>,
+}
+
+/// A struct pattern, e.g. `Foo { x, y: 0, .. }`.
+#[derive(Clone, Debug)]
+pub struct PatStruct {
+    /// The struct path.
+    pub path: TokenStream,
+
+    /// The span of the braces.
+    pub span: Span,
+
+    /// The field patterns.
+    pub fields: Punctuated<FieldPat>,
+
+    /// The span of the trailing `..`, if any.
+    pub rest: Option<Span>,
+}
+
+/// A field in a struct pattern, e.g. `y: 0` or the shorthand `ref x`.
+#[derive(Clone, Debug)]
+pub struct FieldPat {
+    /// The field name.
+    pub member: Member,
+
+    /// The span of the `:` following the member, or `None` for the shorthand form.
+    pub colon: Option<Span>,
+
+    /// The field pattern. For shorthand fields, this is a [`Pat::Ident`] binding the member.
+    pub pat: Pat,
+}
+
+/// A field name in a struct pattern.
+#[derive(Clone, Debug)]
+pub enum Member {
+    /// A named field, e.g. `x`.
+    Named(Ident),
+
+    /// A tuple field index, e.g. `0` in `Foo { 0: a }`.
+    Unnamed(Literal),
+}
+
+/// A tuple struct pattern, e.g. `Some(x)`.
+#[derive(Clone, Debug)]
+pub struct PatTupleStruct {
+    /// The tuple struct path.
+    pub path: TokenStream,
+
+    /// The span of the parentheses.
+    pub span: Span,
+
+    /// The element patterns.
+    pub elems: Punctuated<Pat>,
+}
+
+impl Pat {
+    /// The span of the first token in the pattern.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Ident(pat) => pat
+                .by_ref
+                .as_ref()
+                .or(pat.mutability.as_ref())
+                .unwrap_or(&pat.ident)
+                .span(),
+            Self::Lit(Some(neg), _) => neg.span(),
+            Self::Lit(None, lit) => lit.span(),
+            Self::Path(path) => path.clone().into_iter().next().as_span(),
+            Self::Reference(pat) => pat.and.span(),
+            Self::Rest(span) | Self::Slice(span, _) | Self::Tuple(span, _) => *span,
+            Self::Struct(pat) => pat.path.clone().into_iter().next().as_span(),
+            Self::TupleStruct(pat) => pat.path.clone().into_iter().next().as_span(),
+            Self::Wild(ident) => ident.span(),
+        }
+    }
+
+    /// The identifiers bound by the pattern, in order.
+    ///
+    /// E.g. `a`, `b`, and `c` in `(a, Foo { b, x: c, .. })`.
+    #[must_use]
+    pub fn bindings(&self) -> Vec<&Ident> {
+        let mut bindings = vec![];
+        self.collect_bindings(&mut bindings);
+
+        bindings
+    }
+
+    fn collect_bindings<'a>(&'a self, bindings: &mut Vec<&'a Ident>) {
+        match self {
+            Self::Ident(pat) => {
+                bindings.push(&pat.ident);
+                if let Some((_, subpat)) = &pat.subpat {
+                    subpat.collect_bindings(bindings);
+                }
+            }
+            Self::Reference(pat) => pat.pat.collect_bindings(bindings),
+            Self::Slice(_, elems) | Self::Tuple(_, elems) => {
+                for pat in &elems.items {
+                    pat.collect_bindings(bindings);
+                }
+            }
+            Self::Struct(pat) => {
+                for field in &pat.fields.items {
+                    field.pat.collect_bindings(bindings);
+                }
+            }
+            Self::TupleStruct(pat) => {
+                for pat in &pat.elems.items {
+                    pat.collect_bindings(bindings);
+                }
+            }
+            Self::Lit(..) | Self::Path(_) | Self::Rest(_) | Self::Wild(_) => (),
+        }
+    }
+}
+
+/// A type representing an `impl` block.
+#[derive(Clone, Debug)]
+pub struct ItemImpl {
+    /// Attributes on the `impl` block, followed by inner attributes from the body.
+    pub attrs: Vec<Attribute>,
+
+    /// The `unsafe` qualifier.
+    pub unsafety: Option<Ident>,
+
+    /// The `impl` keyword.
+    pub impl_token: Ident,
+
+    /// Generic parameters and the where clause.
+    pub generics: Generics,
+
+    /// The implemented trait for trait impls, with the `!` for negative impls and the `for`
+    /// keyword.
+    pub trait_: Option<(Option<Punct>, TokenStream, Ident)>,
+
+    /// The type the items are implemented for.
+    pub self_ty: TokenStream,
+
+    /// The span of the braces.
+    pub span: Span,
+
+    /// The associated items.
+    pub items: Vec<ImplItem>,
+}
+
+/// An associated item in an `impl` block.
+#[derive(Clone, Debug)]
+pub enum ImplItem {
+    /// A method or associated function.
+    Fn(Box<ItemFn>),
+
+    /// An associated constant, e.g. `const MAX: u8 = 10;`.
+    Const(RawItem),
+
+    /// An associated type, e.g. `type Item = u8;`.
+    Type(RawItem),
+
+    /// A macro invocation, e.g. `my_macro!();`.
+    Macro(RawItem),
+}
+
+/// Any item, as classified by [`TokenIterExt::parse_item`].
+///
+/// Items without a dedicated parser are kept as [`RawItem`]s.
+#[derive(Clone, Debug)]
+pub enum Item {
+    /// A struct definition.
+    Struct(RawItem),
+
+    /// An enum definition.
+    Enum(RawItem),
+
+    /// A union definition.
+    Union(Union),
+
+    /// A function definition.
+    Fn(Box<ItemFn>),
+
+    /// An `impl` block.
+    Impl(Box<ItemImpl>),
+
+    /// A trait definition.
+    Trait(Box<ItemTrait>),
+
+    /// A module, either inline or declared with `mod foo;`.
+    Mod(RawItem),
+
+    /// A constant item.
+    Const(RawItem),
+
+    /// A static item.
+    Static(RawItem),
+
+    /// A type alias.
+    Type(RawItem),
+
+    /// A `use` declaration.
+    Use(ItemUse),
+
+    /// An `extern crate` declaration. The name is the crate name, not the `as` alias.
+    ExternCrate(RawItem),
+
+    /// An `extern` block, e.g. `extern "C" { ... }`.
+    ForeignMod(ItemForeignMod),
+
+    /// A macro invocation or `macro_rules!` definition.
+    Macro(RawItem),
+}
+
+impl Item {
+    /// The item kind as it is written in source, e.g. `"struct"` or `"fn"`.
+    ///
+    /// Macro invocations are reported as `"macro"`.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Struct(_) => "struct",
+            Self::Enum(_) => "enum",
+            Self::Union(_) => "union",
+            Self::Fn(_) => "fn",
+            Self::Impl(_) => "impl",
+            Self::Trait(_) => "trait",
+            Self::Mod(_) => "mod",
+            Self::Const(_) => "const",
+            Self::Static(_) => "static",
+            Self::Type(_) => "type",
+            Self::Use(_) => "use",
+            Self::ExternCrate(_) | Self::ForeignMod(_) => "extern",
+            Self::Macro(_) => "macro",
+        }
+    }
+
+    /// The span of the item name, for use in error messages.
+    ///
+    /// `impl` blocks use the span of the self type, `use` declarations use the span of the
+    /// path, and `extern` blocks use the span of the `extern` keyword.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Struct(item)
+            | Self::Enum(item)
+            | Self::Mod(item)
+            | Self::Const(item)
+            | Self::Static(item)
+            | Self::Type(item)
+            | Self::ExternCrate(item)
+            | Self::Macro(item) => item.name.span(),
+            Self::Use(item) => item
+                .tree
+                .clone()
+                .into_iter()
+                .next()
+                .map_or_else(|| item.use_token.span(), |tree| tree.span()),
+            Self::ForeignMod(item) => item.abi.0.span(),
+            Self::Union(item) => item.name.span(),
+            Self::Fn(item) => item.sig.name.span(),
+            Self::Impl(item) => item.self_ty.clone().into_iter().next().as_span(),
+            Self::Trait(item) => item.name.span(),
+        }
+    }
+}
+
+/// A `use` declaration, e.g. `use std::{fmt, io};`.
+#[derive(Clone, Debug)]
+pub struct ItemUse {
+    /// Attributes on the declaration.
+    pub attrs: Vec<Attribute>,
+
+    /// The declaration visibility.
+    pub vis: Visibility,
+
+    /// The `use` keyword.
+    pub use_token: Ident,
+
+    /// The imported path, e.g. `std::{fmt, io}`.
+    pub tree: TokenStream,
+
+    /// The span of the trailing `;`.
+    pub semi: Span,
+}
+
+/// An `extern` block, e.g. `extern "C" { fn abs(x: i32) -> i32; }`.
+#[derive(Clone, Debug)]
+pub struct ItemForeignMod {
+    /// Attributes on the block.
+    pub attrs: Vec<Attribute>,
+
+    /// The `unsafe` qualifier.
+    pub unsafety: Option<Ident>,
+
+    /// The `extern` keyword and optional ABI string.
+    pub abi: (Ident, Option<Literal>),
+
+    /// The block body, as an untouched `{...}` group.
+    pub body: Group,
+}
+
+/// A type representing a trait definition.
+#[derive(Clone, Debug)]
+pub struct ItemTrait {
+    /// Attributes on the trait, followed by inner attributes from the body.
+    pub attrs: Vec<Attribute>,
+
+    /// The trait visibility.
+    pub vis: Visibility,
+
+    /// The `unsafe` qualifier.
+    pub unsafety: Option<Ident>,
+
+    /// The `trait` keyword.
+    pub trait_token: Ident,
+
+    /// The trait name.
+    pub name: Ident,
+
+    /// Generic parameters and the where clause.
+    pub generics: Generics,
+
+    /// The span of the `:` before the supertrait bounds.
+    pub colon: Option<Span>,
+
+    /// Supertrait bounds following the `:`, e.g. `Clone + Send`.
+    pub supertraits: TokenStream,
+
+    /// The span of the braces.
+    pub span: Span,
+
+    /// The associated items.
+    pub items: Vec<TraitItem>,
+}
+
+/// An associated item in a trait definition.
+#[derive(Clone, Debug)]
+pub enum TraitItem {
+    /// A method or associated function.
+    Fn(Box<TraitItemFn>),
+
+    /// An associated type, e.g. `type Item: Clone;`.
+    Type(TraitItemType),
+
+    /// An associated constant, e.g. `const MAX: u8;`.
+    Const(TraitItemConst),
+
+    /// A macro invocation, e.g. `my_macro!();`.
+    Macro(RawItem),
+}
+
+/// A method or associated function in a trait definition.
+#[derive(Clone, Debug)]
+pub struct TraitItemFn {
+    /// Attributes on the function.
+    pub attrs: Vec<Attribute>,
+
+    /// The function signature.
+    pub sig: Signature,
+
+    /// The default body, including braces.
+    pub default: Option<Group>,
+
+    /// The span of the trailing `;` when there is no default body.
+    pub semi: Option<Span>,
+}
+
+/// An associated type in a trait definition.
+#[derive(Clone, Debug)]
+pub struct TraitItemType {
+    /// Attributes on the type.
+    pub attrs: Vec<Attribute>,
+
+    /// The `type` keyword.
+    pub type_token: Ident,
+
+    /// The type name.
+    pub name: Ident,
+
+    /// Generic parameters and the where clause.
+    pub generics: Generics,
+
+    /// The span of the `:` before the bounds.
+    pub colon: Option<Span>,
+
+    /// Trait bounds following the `:`, e.g. `Clone + 'static`.
+    pub bounds: TokenStream,
+
+    /// The span of the `=` before the default type.
+    pub eq: Option<Span>,
+
+    /// The default type.
+    pub default: Option<TokenStream>,
+
+    /// The span of the trailing `;`.
+    pub semi: Span,
+}
+
+/// An associated constant in a trait definition.
+#[derive(Clone, Debug)]
+pub struct TraitItemConst {
+    /// Attributes on the constant.
+    pub attrs: Vec<Attribute>,
+
+    /// The `const` keyword.
+    pub const_token: Ident,
+
+    /// The constant name.
+    pub name: Ident,
+
+    /// The span of the `:` between the name and type.
+    pub colon: Span,
+
+    /// The constant type.
+    pub ty: TokenStream,
+
+    /// The span of the `=` before the default value.
+    pub eq: Option<Span>,
+
+    /// The default value expression.
+    pub default: Option<TokenStream>,
+
+    /// The span of the trailing `;`.
+    pub semi: Span,
+}
+
+/// An item kept as raw tokens.
+#[derive(Clone, Debug)]
+pub struct RawItem {
+    /// Attributes on the item.
+    pub attrs: Vec<Attribute>,
+
+    /// The item visibility.
+    pub vis: Visibility,
+
+    /// The item name, e.g. `MAX` in `const MAX: u8 = 10;`.
+    ///
+    /// For macro invocations, this is the last segment of the macro path.
+    pub name: Ident,
+
+    /// The item tokens following the attributes and visibility, including the trailing `;`.
+    pub tokens: TokenStream,
+}
+
+/// A configuration predicate from `#[cfg(...)]` or `#[cfg_attr(...)]`.
+#[derive(Clone, Debug)]
+pub enum CfgPredicate {
+    /// A configuration option, e.g. `unix`.
+    Option(Ident),
+
+    /// A key-value pair, e.g. `feature = "std"`.
+    KeyValue(Ident, Literal),
+
+    /// `all(...)`, true when all predicates are true.
+    All(Vec<CfgPredicate>),
+
+    /// `any(...)`, true when any predicate is true.
+    Any(Vec<CfgPredicate>),
+
+    /// `not(...)`, true when the predicate is false.
+    Not(Box<CfgPredicate>),
+}
+
+/// A type representing `#[cfg_attr(predicate, attributes...)]`.
+#[derive(Clone, Debug)]
+pub struct CfgAttr {
+    /// The span of the `cfg_attr` name.
+    pub span: Span,
+
+    /// Whether this was an outer or inner attribute. The contained attributes share this style.
+    pub style: AttrStyle,
+
+    /// The configuration predicate.
+    pub predicate: CfgPredicate,
+
+    /// The attributes applied when the predicate is true.
+    pub attrs: Vec<Attribute>,
+}
+
+/// A type representing the combined `#[repr(...)]` attributes, created by [`parse_repr`].
+///
+/// [`parse_repr`]: crate::utils::parse_repr
+#[derive(Clone, Debug, Default)]
+pub struct Repr {
+    /// The primitive integer representation, e.g. `u8` in `#[repr(u8)]`.
+    pub int: Option<(IntType, Span)>,
+
+    /// The span of `C` in `#[repr(C)]`.
+    pub c: Option<Span>,
+
+    /// The span of `transparent` in `#[repr(transparent)]`.
+    pub transparent: Option<Span>,
+
+    /// The alignment for `#[repr(packed)]` or `#[repr(packed(N))]`.
+    ///
+    /// Plain `packed` has an alignment of 1.
+    pub packed: Option<(u32, Span)>,
+
+    /// The alignment for `#[repr(align(N))]`.
+    pub align: Option<(u32, Span)>,
+}
+
+/// Primitive integer types usable in `#[repr(...)]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IntType {
+    /// `i8`
+    I8,
+    /// `i16`
+    I16,
+    /// `i32`
+    I32,
+    /// `i64`
+    I64,
+    /// `i128`
+    I128,
+    /// `isize`
+    Isize,
+    /// `u8`
+    U8,
+    /// `u16`
+    U16,
+    /// `u32`
+    U32,
+    /// `u64`
+    U64,
+    /// `u128`
+    U128,
+    /// `usize`
+    Usize,
+}
+
+impl IntType {
+    const NAMES: [(&'static str, IntType); 12] = [
+        ("i8", IntType::I8),
+        ("i16", IntType::I16),
+        ("i32", IntType::I32),
+        ("i64", IntType::I64),
+        ("i128", IntType::I128),
+        ("isize", IntType::Isize),
+        ("u8", IntType::U8),
+        ("u16", IntType::U16),
+        ("u32", IntType::U32),
+        ("u64", IntType::U64),
+        ("u128", IntType::U128),
+        ("usize", IntType::Usize),
+    ];
+
+    /// Get the integer type by name, e.g. `"u8"`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(int_name, _)| *int_name == name)
+            .map(|(_, int)| *int)
+    }
+
+    /// Get the name of this integer type, e.g. `"u8"`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, int)| *int == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` for signed integer types.
+    #[must_use]
+    pub fn is_signed(self) -> bool {
+        self.name().starts_with('i')
+    }
+
+    /// The number of bits in this type.
+    ///
+    /// `isize` and `usize` are assumed to be 64 bits.
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::I8 | Self::U8 => 8,
+            Self::I16 | Self::U16 => 16,
+            Self::I32 | Self::U32 => 32,
+            Self::I64 | Self::Isize | Self::U64 | Self::Usize => 64,
+            Self::I128 | Self::U128 => 128,
+        }
+    }
+
+    /// The minimum value of this type.
+    ///
+    /// `isize` and `usize` are assumed to be 64 bits.
+    #[must_use]
+    pub fn min(self) -> i128 {
+        match self {
+            Self::I8 => i8::MIN.into(),
+            Self::I16 => i16::MIN.into(),
+            Self::I32 => i32::MIN.into(),
+            Self::I64 | Self::Isize => i64::MIN.into(),
+            Self::I128 => i128::MIN,
+            _ => 0,
+        }
+    }
+
+    /// The maximum value of this type.
+    ///
+    /// `isize` and `usize` are assumed to be 64 bits. `u128` is limited to `i128::MAX`.
+    #[must_use]
+    pub fn max(self) -> i128 {
+        match self {
+            Self::I8 => i8::MAX.into(),
+            Self::I16 => i16::MAX.into(),
+            Self::I32 => i32::MAX.into(),
+            Self::I64 | Self::Isize => i64::MAX.into(),
+            Self::U8 => u8::MAX.into(),
+            Self::U16 => u16::MAX.into(),
+            Self::U32 => u32::MAX.into(),
+            Self::U64 | Self::Usize => u64::MAX.into(),
+            Self::I128 | Self::U128 => i128::MAX,
+        }
+    }
+}
+
+/// Normalized doc comment text, created by [`parse_doc_comment`].
+///
+/// [`parse_doc_comment`]: crate::utils::parse_doc_comment
+#[derive(Debug, Default)]
+pub struct DocComment {
+    /// Lines of documentation with common indentation removed.
+    pub lines: Vec<DocLine>,
+
+    /// Doc attribute values which are macro invocations, like `include_str!("README.md")`.
+    ///
+    /// These cannot be evaluated by a procedural macro, but they can be re-emitted.
+    pub macros: Vec<TokenStream>,
+}
+
+/// A single line of documentation.
+#[derive(Debug)]
+pub struct DocLine {
+    /// The line text, without a trailing newline.
+    pub text: String,
+
+    /// The span of the doc attribute containing this line.
+    pub span: Span,
+}
+
+impl DocComment {
+    /// Returns `true` if there are no lines of documentation.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The full text, with lines separated by newlines.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The text split into paragraphs at blank lines, with each paragraph's lines joined by spaces.
+    #[must_use]
+    pub fn paragraphs(&self) -> Vec<String> {
+        let mut paragraphs = vec![];
+        let mut current: Vec<&str> = vec![];
+
+        for line in &self.lines {
+            let text = line.text.trim();
+            if text.is_empty() {
+                if !current.is_empty() {
+                    paragraphs.push(current.join(" "));
+                    current.clear();
+                }
+            } else {
+                current.push(text);
+            }
+        }
+        if !current.is_empty() {
+            paragraphs.push(current.join(" "));
+        }
+
+        paragraphs
+    }
+
+    /// The first paragraph, with its lines joined by spaces.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        self.paragraphs().into_iter().next().unwrap_or_default()
+    }
+
+    /// Everything after the first paragraph, with lines separated by newlines.
+    #[must_use]
+    pub fn body(&self) -> String {
+        let mut lines = self
+            .lines
+            .iter()
+            .skip_while(|line| line.text.trim().is_empty());
+        lines
+            .by_ref()
+            .take_while(|line| !line.text.trim().is_empty())
+            .for_each(drop);
+
+        lines
+            .skip_while(|line| line.text.trim().is_empty())
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl TokenIterExt for TokenIter {
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, TokenStream> {
+        let mut attrs = vec![];
+
+        while self.peek_is(token::Op("#")) {
+            attrs.push(Attribute::parse(self)?);
+        }
+
+        Ok(attrs)
+    }
+
+    fn parse_inner_attributes(&mut self) -> Result<Vec<Attribute>, TokenStream> {
+        let mut attrs = vec![];
+
+        loop {
+            let mut lookahead = self.fork();
+            match (lookahead.next(), lookahead.next()) {
+                (Some(TokenTree::Punct(pound)), Some(TokenTree::Punct(bang)))
+                    if pound.as_char() == '#' && bang.as_char() == '!' =>
+                {
+                    self.next();
+                    self.next();
+                }
+                _ => break,
+            }
+
+            let mut group = self.expect_group(Delimiter::Bracket)?;
+            let ident = group.try_ident()?;
+
+            attrs.push(Attribute {
+                name: ident,
+                tree: group.collect::<TokenStream>().into_token_iter(),
+                style: AttrStyle::Inner,
+            });
+        }
+
+        Ok(attrs)
+    }
+
+    fn parse_visibility(&mut self) -> Result<(), TokenStream> {
+        self.parse_visibility_full().map(drop)
+    }
+
+    fn parse_visibility_full(&mut self) -> Result<Visibility, TokenStream> {
+        let ident = match self.peek() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => self.try_ident()?,
+            _ => return Ok(Visibility::Inherited),
+        };
+
+        // A parenthesized group is a restriction only if it is `(crate)`, `(self)`, `(super)`,
+        // or `(in path)`. Otherwise it belongs to what follows, e.g. the tuple field `pub (u8, u8)`.
+        let mut fork = self.fork();
+        if let Ok(group) = fork.try_group() {
+            let mut inner = group.stream().into_token_iter();
+            let restricted = group.delimiter() == Delimiter::Parenthesis
+                && match inner.next() {
+                    Some(TokenTree::Ident(ident)) => match ident.to_string().as_str() {
+                        "crate" | "self" | "super" => inner.peek().is_none(),
+                        "in" => inner.peek().is_some(),
+                        _ => false,
+                    },
+                    _ => false,
+                };
+
+            if restricted {
+                self.advance_to(&fork);
+                return Ok(Visibility::Restricted(ident, group));
+            }
+        }
+
+        Ok(Visibility::Public(ident))
+    }
+
+    fn parse_path(&mut self) -> Result<(String, Span), TokenStream> {
+        let mut path = String::new();
+        let mut span = None;
+        let mut nesting = 0_usize;
+
+        while let Some(tree) = self.peek() {
+            match tree {
+                TokenTree::Punct(punct)
+                    if [',', ';'].contains(&punct.as_char()) && nesting == 0 =>
+                {
+                    break
+                }
+                TokenTree::Punct(punct) => {
+                    let ch = punct.as_char();
+                    span.get_or_insert_with(|| punct.span());
+
+                    // Handle nesting with `<...>`, where the `>` in `->` does not count.
+                    if self.peek_op("->") {
+                        self.next();
+                        path.push_str("->");
+                    } else {
+                        if ch == '<' {
+                            nesting += 1;
+                        } else if ch == '>' {
+                            nesting = nesting.saturating_sub(1);
+                        }
+                        path.push(ch);
+                    }
+                }
+                TokenTree::Ident(ident) => {
+                    span.get_or_insert_with(|| ident.span());
+
+                    // Separate adjacent words, e.g. `&'a str` or `dyn Trait`.
+                    if path.ends_with(|ch: char| ch.is_alphanumeric() || ch == '_') {
+                        path.push(' ');
+                    }
+                    path.push_str(&ident.to_string());
+                }
+                TokenTree::Group(group) => {
+                    span.get_or_insert(group.span());
+                    let mut stream = group.stream().into_token_iter();
+
+                    match group.delimiter() {
+                        Delimiter::Parenthesis => {
+                            // Tuples are comma-separated paths.
+                            let paths = stream.parse_punctuated(',', TokenIterExt::parse_path)?;
+                            let trailing = if paths.has_trailing() { "," } else { "" };
+                            let inner = paths.into_iter().map(|(inner, _span)| inner);
+                            path.push('(');
+                            path.push_str(&inner.collect::<Vec<_>>().join(", "));
+                            path.push_str(trailing);
+                            path.push(')');
+                        }
+                        Delimiter::Bracket => {
+                            // Arrays are in `[path; size]` form.
+                            path.push('[');
+                            let (inner, _span) = stream.parse_path()?;
+                            path.push_str(&inner);
+                            stream.expect_punct(';')?;
+                            path.push_str("; ");
+                            path.push_str(&stream.try_lit()?.to_string());
+                            path.push(']');
+                        }
+                        _ => return Err(spanned_error("Unexpected token", group.span())),
+                    }
+                }
+                TokenTree::Literal(_) => {
+                    return Err(spanned_error("Unexpected token", self.next().as_span()))
+                }
+            }
+
+            self.next();
+        }
+
+        let span =
+            span.ok_or_else(|| spanned_error("Unexpected end of stream", Span::call_site()))?;
+
+        Ok((path, span))
+    }
+
+    fn parse_type(&mut self) -> Result<TokenStream, TokenStream> {
+        let span = self.peek().cloned().as_span();
+        let ty = collect_until(self, &[',', ';', '=', '>'], &["where"]);
+
+        if ty.is_empty() {
+            Err(spanned_error("Expected type", span))
+        } else {
+            Ok(ty)
+        }
+    }
+
+    fn parse_expr_tokens(&mut self) -> Result<TokenStream, TokenStream> {
+        let span = self.peek().cloned().as_span();
+        let mut tokens = TokenStream::new();
+        let mut nesting = 0_usize;
+        let mut operand = false;
+        let mut path_sep = false;
+        let mut closure = false;
+        let mut joint_pipe = false;
+
+        while let Some(tree) = self.peek() {
+            let mut after_path_sep = false;
+            let is_joint_pipe = matches!(
+                tree,
+                TokenTree::Punct(punct) if punct.as_char() == '|' && punct.spacing() == Spacing::Joint
+            );
+
+            match tree {
+                // Closure parameters may contain commas, e.g. `|a, b| a + b`.
+                TokenTree::Punct(punct) if closure => {
+                    closure = punct.as_char() != '|';
+                    operand = false;
+                }
+                TokenTree::Punct(_)
+                    if nesting == 0
+                        && (self.peek_op(",") || self.peek_op(";") || self.peek_op("=>")) =>
+                {
+                    break;
+                }
+                TokenTree::Punct(_) if self.peek_op("::") || self.peek_op("->") => {
+                    after_path_sep = self.peek_op("::");
+                    tokens.extend(self.next());
+                    operand = false;
+                }
+                TokenTree::Punct(punct) => {
+                    let ch = punct.as_char();
+
+                    // A `<` is a comparison after an operand, unless it starts a turbofish.
+                    if ch == '<' && (nesting > 0 || path_sep || !operand) {
+                        nesting += 1;
+                        operand = false;
+                    } else if ch == '>' && nesting > 0 {
+                        nesting -= 1;
+                        operand = true;
+                    } else if ch == '|' && nesting == 0 && !operand && !joint_pipe {
+                        // The second half of a joint `||` is not a closure start, e.g. in `a || b`.
+                        closure = true;
+                    } else {
+                        operand = ch == '?';
+                    }
+                }
+                TokenTree::Ident(ident) => {
+                    operand = !EXPR_KEYWORDS.contains(&ident.to_string().as_str());
+                }
+                TokenTree::Group(_) | TokenTree::Literal(_) => operand = true,
+            }
+
+            path_sep = after_path_sep;
+            joint_pipe = is_joint_pipe;
+            tokens.extend(self.next());
+        }
+
+        if tokens.is_empty() {
+            Err(spanned_error("Expected expression", span))
+        } else {
+            Ok(tokens)
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, TokenStream> {
+        let tokens = self.parse_expr_tokens()?;
+
+        Ok(Expr {
+            kind: classify_expr(&tokens),
+            tokens,
+        })
+    }
+
+    fn parse_pat(&mut self) -> Result<Pat, TokenStream> {
+        match self.peek() {
+            Some(TokenTree::Literal(_)) => return Ok(Pat::Lit(None, self.try_lit()?)),
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '-' => {
+                let neg = self.try_punct()?;
+                return Ok(Pat::Lit(Some(neg), self.try_lit()?));
+            }
+            Some(TokenTree::Punct(_)) if self.peek_op("..") => {
+                return Ok(Pat::Rest(self.expect_op("..")?));
+            }
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '&' => {
+                let and = self.try_punct()?;
+                let mutability = take_keyword(self, "mut");
+                let pat = Box::new(self.parse_pat()?);
+
+                return Ok(Pat::Reference(PatReference {
+                    and,
+                    mutability,
+                    pat,
+                }));
+            }
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                let (span, elems) = parse_pat_list(self)?;
+                return Ok(Pat::Tuple(span, elems));
+            }
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                let (span, elems) = parse_pat_list(self)?;
+                return Ok(Pat::Slice(span, elems));
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "_" => {
+                return Ok(Pat::Wild(self.try_ident()?));
+            }
+            Some(TokenTree::Ident(_)) if self.peek_keyword(&["ref", "mut"]).is_some() => {
+                return parse_pat_ident(self).map(Pat::Ident);
+            }
+            Some(TokenTree::Punct(_)) if self.peek_op("::") || self.peek_op("<") => (),
+            Some(TokenTree::Ident(_)) => (),
+            tree => return Err(spanned_error("Expected pattern", tree.cloned().as_span())),
+        }
+
+        // Paths, tuple structs, structs, and identifiers.
+        let (path, segments) = parse_pat_path(self)?;
+
+        match self.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                let (span, elems) = parse_pat_list(self)?;
+                Ok(Pat::TupleStruct(PatTupleStruct { path, span, elems }))
+            }
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                let group = self.try_group()?;
+                let mut inner = group.stream().into_token_iter();
+                let mut fields = Punctuated {
+                    items: vec![],
+                    separators: vec![],
+                };
+                let mut rest = None;
+
+                while inner.peek().is_some() {
+                    if inner.peek_op("..") {
+                        rest = Some(inner.expect_op("..")?);
+                        inner.expect_end()?;
+                        break;
+                    }
+
+                    fields.items.push(parse_field_pat(&mut inner)?);
+                    if inner.peek().is_some() {
+                        fields.separators.push(expect_punct_span(&mut inner, ',')?);
+                    }
+                }
+
+                Ok(Pat::Struct(PatStruct {
+                    path,
+                    span: group.span(),
+                    fields,
+                    rest,
+                }))
+            }
+            _ if segments > 1 => Ok(Pat::Path(path)),
+            _ => {
+                let ident = match path.into_iter().next() {
+                    Some(TokenTree::Ident(ident)) => ident,
+                    tree => return Err(spanned_error("Expected pattern", tree.as_span())),
+                };
+
+                match ident.to_string().as_str() {
+                    "true" | "false" => Ok(Pat::Path(TokenStream::from(TokenTree::Ident(ident)))),
+                    _ => Ok(Pat::Ident(PatIdent {
+                        by_ref: None,
+                        mutability: None,
+                        ident,
+                        subpat: parse_subpat(self)?,
+                    })),
+                }
+            }
+        }
+    }
+
+    fn parse_generics(&mut self) -> Result<Generics, TokenStream> {
+        let mut generics = Generics::default();
+        let lt = match self.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => self.try_punct()?.span(),
+            _ => return Ok(generics),
+        };
+
+        loop {
+            match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '>' => {
+                    generics.brackets = Some((lt, self.try_punct()?.span()));
+                    break;
+                }
+                None => return Err(spanned_error("Expected `>`", Span::call_site())),
+                _ => (),
+            }
+
+            let attrs = self.parse_attributes()?;
+            let mut colon = None;
+            let (kind, name) = match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '\'' => {
+                    (GenericParamKind::Lifetime, self.try_lifetime()?.ident)
+                }
+                Some(TokenTree::Ident(ident)) if ident.to_string() == "const" => {
+                    self.next();
+                    let name = self.try_ident()?;
+                    colon = Some(expect_punct_span(self, ':')?);
+                    (GenericParamKind::Const(self.parse_type()?), name)
+                }
+                _ => (GenericParamKind::Type, self.try_ident()?),
+            };
+
+            let bounds = match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => {
+                    colon = Some(self.try_punct()?.span());
+                    collect_until(self, &[',', '=', '>'], &[])
+                }
+                _ => TokenStream::new(),
+            };
+
+            let mut eq = None;
+            let default = match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                    eq = Some(self.try_punct()?.span());
+                    match self.peek() {
+                        Some(TokenTree::Group(_)) => Some(self.next().into_iter().collect()),
+                        _ => Some(self.parse_type()?),
+                    }
+                }
+                _ => None,
+            };
+
+            let comma = match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '>' => None,
+                _ => Some(expect_punct_span(self, ',')?),
+            };
+
+            generics.params.push(GenericParam {
+                attrs,
+                kind,
+                name,
+                bounds,
+                default,
+                colon,
+                eq,
+                comma,
+            });
+        }
+
+        Ok(generics)
+    }
+
+    fn parse_where_clause(&mut self) -> Result<Vec<TokenStream>, TokenStream> {
+        let mut predicates = vec![];
+        match self.peek() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "where" => self.next(),
+            _ => return Ok(predicates),
+        };
+
+        loop {
+            let predicate = collect_until(self, &[',', ';'], &[]);
+            if !predicate.is_empty() {
+                predicates.push(predicate);
+            }
+
+            match self.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => self.next(),
+                _ => break,
+            };
+        }
+
+        Ok(predicates)
+    }
+
+    fn parse_fields(&mut self) -> Result<Vec<Field>, TokenStream> {
+        let fields = self.parse_punctuated(',', |iter| {
+            let attrs = iter.parse_attributes()?;
+            let vis = iter.parse_visibility_full()?;
+            let name = iter.try_ident()?;
+            let colon = expect_punct_span(iter, ':')?;
+            let ty = iter.parse_type()?;
+
+            Ok(Field {
+                attrs,
+                vis,
+                name,
+                colon,
+                ty,
+            })
+        })?;
+
+        Ok(fields.items)
+    }
+
+    fn parse_union(&mut self) -> Result<Union, TokenStream> {
+        let attrs = self.parse_attributes()?;
+        let vis = self.parse_visibility_full()?;
+        self.expect_ident("union")?;
+        let name = self.try_ident()?;
+        let mut generics = self.parse_generics()?;
+        generics.where_clause = self.parse_where_clause()?;
+        let group = self.try_group()?;
+        if group.delimiter() != Delimiter::Brace {
+            return Err(spanned_error("Expected `{`", group.span()));
+        }
+        let fields = group.stream().into_token_iter().parse_fields()?;
+
+        Ok(Union {
+            attrs,
+            vis,
+            name,
+            generics,
+            fields,
+            span: group.span(),
+        })
+    }
+
+    fn parse_signature(&mut self) -> Result<Signature, TokenStream> {
+        let constness = take_keyword(self, "const");
+        let asyncness = take_keyword(self, "async");
+        let unsafety = take_keyword(self, "unsafe");
+        let abi = take_keyword(self, "extern").map(|ident| (ident, self.try_lit().ok()));
+        let fn_token = self.expect_one_of(&["fn"])?;
+        let name = self.try_ident()?;
+        let mut generics = self.parse_generics()?;
+
+        let (paren, mut args) = expect_delimited(self, Delimiter::Parenthesis)?;
+        let inputs = args.parse_punctuated(',', parse_fn_arg)?;
+
+        let output = if self.peek_op("->") {
+            let arrow = self.expect_op("->")?;
+            Some((arrow, self.parse_type()?))
+        } else {
+            None
+        };
+        generics.where_clause = self.parse_where_clause()?;
+
+        Ok(Signature {
+            constness,
+            asyncness,
+            unsafety,
+            abi,
+            fn_token,
+            name,
+            generics,
+            paren,
+            inputs,
+            output,
+        })
+    }
+
+    fn parse_fn(&mut self) -> Result<ItemFn, TokenStream> {
+        let attrs = self.parse_attributes()?;
+        let vis = self.parse_visibility_full()?;
+        let sig = self.parse_signature()?;
+
+        let body = match self.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                self.try_group()?
+            }
+            tree => return Err(spanned_error("Expected `{`", tree.cloned().as_span())),
+        };
+
+        Ok(ItemFn {
+            attrs,
+            vis,
+            defaultness: None,
+            sig,
+            body,
+        })
+    }
+
+    fn parse_impl(&mut self) -> Result<ItemImpl, TokenStream> {
+        let mut attrs = self.parse_attributes()?;
+        let unsafety = take_keyword(self, "unsafe");
+        let impl_token = self.expect_one_of(&["impl"])?;
+        let mut generics = self.parse_generics()?;
+
+        let negative = match self.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '!' => Some(self.try_punct()?),
+            _ => None,
+        };
+        let span = self.peek().cloned().as_span();
+        let first = collect_until(self, &[';'], &["for", "where"]);
+        if first.is_empty() {
+            return Err(spanned_error("Expected type", span));
+        }
+
+        let (trait_, self_ty) = if let Some(for_token) = take_keyword(self, "for") {
+            (Some((negative, first, for_token)), self.parse_type()?)
+        } else if let Some(negative) = negative {
+            return Err(spanned_error("Expected `for`", negative.span()));
+        } else {
+            (None, first)
+        };
+        generics.where_clause = self.parse_where_clause()?;
+
+        let (span, mut body) = expect_delimited(self, Delimiter::Brace)?;
+        attrs.extend(body.parse_inner_attributes()?);
+        let mut items = vec![];
+        while body.peek().is_some() {
+            items.push(parse_impl_item(&mut body)?);
+        }
+
+        Ok(ItemImpl {
+            attrs,
+            unsafety,
+            impl_token,
+            generics,
+            trait_,
+            self_ty,
+            span,
+            items,
+        })
+    }
+
+    fn parse_trait(&mut self) -> Result<ItemTrait, TokenStream> {
+        let mut attrs = self.parse_attributes()?;
+        let vis = self.parse_visibility_full()?;
+        let unsafety = take_keyword(self, "unsafe");
+        let trait_token = self.expect_one_of(&["trait"])?;
+        let name = self.try_ident()?;
+        let mut generics = self.parse_generics()?;
+
+        let colon = take_punct(self, ':');
+        let supertraits = match colon {
+            Some(_) => collect_until(self, &[], &["where"]),
+            None => TokenStream::new(),
+        };
+        generics.where_clause = self.parse_where_clause()?;
+
+        let (span, mut body) = expect_delimited(self, Delimiter::Brace)?;
+        attrs.extend(body.parse_inner_attributes()?);
+        let mut items = vec![];
+        while body.peek().is_some() {
+            items.push(parse_trait_item(&mut body)?);
+        }
+
+        Ok(ItemTrait {
+            attrs,
+            vis,
+            unsafety,
+            trait_token,
+            name,
+            generics,
+            colon,
+            supertraits,
+            span,
+            items,
+        })
+    }
+
+    fn parse_item(&mut self) -> Result<Item, TokenStream> {
+        let mut lookahead = self.fork();
+        lookahead.parse_attributes()?;
+        lookahead.parse_visibility()?;
+
+        if is_fn(&lookahead) {
+            return self.parse_fn().map(|item| Item::Fn(Box::new(item)));
+        }
+        take_keyword(&mut lookahead, "unsafe");
+        let keyword = match lookahead.peek() {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            tree => return Err(spanned_error("Expected item", tree.cloned().as_span())),
+        };
+        match keyword.as_str() {
+            "union" => return self.parse_union().map(Item::Union),
+            "impl" => return self.parse_impl().map(|item| Item::Impl(Box::new(item))),
+            "trait" => return self.parse_trait().map(|item| Item::Trait(Box::new(item))),
+            _ => (),
+        }
+
+        let attrs = self.parse_attributes()?;
+        let vis = self.parse_visibility_full()?;
+        let name = match keyword.as_str() {
+            "struct" | "enum" | "mod" | "const" | "static" | "type" => {
+                lookahead.next();
+                take_keyword(&mut lookahead, "mut");
+                lookahead.try_ident()?
+            }
+            "use" => {
+                let use_token = self.try_ident()?;
+                let mut tree = TokenStream::new();
+                while let Some(next) =
+                    self.next_if(|tree| !matches!(tree, TokenTree::Punct(p) if p.as_char() == ';'))
+                {
+                    tree.extend([next]);
+                }
+                let semi = expect_punct_span(self, ';')?;
+                return Ok(Item::Use(ItemUse {
+                    attrs,
+                    vis,
+                    use_token,
+                    tree,
+                    semi,
+                }));
+            }
+            "extern" => {
+                lookahead.next();
+                if take_keyword(&mut lookahead, "crate").is_some() {
+                    lookahead.try_ident()?
+                } else {
+                    let unsafety = take_keyword(self, "unsafe");
+                    let abi = (self.try_ident()?, self.try_lit().ok());
+                    let body = self.try_group()?;
+                    if body.delimiter() != Delimiter::Brace {
+                        return Err(spanned_error("Expected `{`", body.span()));
+                    }
+                    return Ok(Item::ForeignMod(ItemForeignMod {
+                        attrs,
+                        unsafety,
+                        abi,
+                        body,
+                    }));
+                }
+            }
+            _ => return parse_macro_item(self, attrs, vis).map(Item::Macro),
+        };
+
+        let tokens = match keyword.as_str() {
+            "struct" | "enum" | "mod" => collect_definition(self),
+            _ => collect_item(self),
+        };
+        let item = RawItem {
+            attrs,
+            vis,
+            name,
+            tokens,
+        };
+
+        Ok(match keyword.as_str() {
+            "struct" => Item::Struct(item),
+            "enum" => Item::Enum(item),
+            "mod" => Item::Mod(item),
+            "const" => Item::Const(item),
+            "static" => Item::Static(item),
+            "type" => Item::Type(item),
+            _ => Item::ExternCrate(item),
+        })
+    }
+
+    fn parse_cfg_predicate(&mut self) -> Result<CfgPredicate, TokenStream> {
+        let ident = self.try_ident()?;
+
+        match self.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                self.next();
+                let lit = self.try_lit()?;
+                return Ok(CfgPredicate::KeyValue(ident, lit));
+            }
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => (),
+            _ => return Ok(CfgPredicate::Option(ident)),
+        }
+
+        let name = ident.to_string();
+        if !["all", "any", "not"].contains(&name.as_str()) {
+            return Err(spanned_error(
+                format!("Unknown cfg predicate `{name}`"),
+                ident.span(),
+            ));
+        }
+
+        let mut predicates = self
+            .expect_group(Delimiter::Parenthesis)?
+            .parse_punctuated(',', TokenIterExt::parse_cfg_predicate)?
+            .items;
+
+        match name.as_str() {
+            "all" => Ok(CfgPredicate::All(predicates)),
+            "any" => Ok(CfgPredicate::Any(predicates)),
+            _ if predicates.len() == 1 => Ok(CfgPredicate::Not(Box::new(predicates.remove(0)))),
+            _ => Err(spanned_error(
+                "Expected exactly one cfg predicate",
+                ident.span(),
+            )),
+        }
+    }
+
+    fn expect_group(&mut self, expect: Delimiter) -> Result<TokenIter, TokenStream> {
+        self.try_group()
+            .and_then(|group| {
+                let delim = group.delimiter();
+                if delim == expect {
+                    Ok(group.stream().into_token_iter())
+                } else {
+                    let expect = match expect {
+                        Delimiter::Brace => "{",
+                        Delimiter::Bracket => "[",
+                        Delimiter::None => "delimiter",
+                        Delimiter::Parenthesis => "(",
+                    };
+
+                    Err(spanned_error(format!("Expected `{expect}`"), group.span()))
+                }
+            })
+            .map_err(|err| {
+                self.next();
+                err
+            })
+    }
+
+    fn expect_ident(&mut self, expect: &str) -> Result<(), TokenStream> {
+        self.try_ident()
+            .and_then(|ident| {
+                if ident.to_string() == expect {
+                    Ok(())
+                } else {
+                    Err(spanned_error(format!("Expected `{expect}`"), ident.span()))
+                }
+            })
+            .map_err(|err| {
+                self.next();
+                err
+            })
+    }
+
+    fn expect_punct(&mut self, expect: char) -> Result<(), TokenStream> {
+        self.try_punct()
+            .and_then(|punct| {
+                if punct.as_char() == expect {
+                    Ok(())
+                } else {
+                    Err(spanned_error(format!("Expected `{expect}`"), punct.span()))
+                }
+            })
+            .map_err(|err| {
+                self.next();
+                err
+            })
+    }
+
+    fn expect_lifetime(&mut self, expect: &str) -> Result<(), TokenStream> {
+        let expect = expect.trim_start_matches('\'');
+
+        match self.try_lifetime() {
+            Ok(lifetime) if lifetime.ident.to_string() == expect => Ok(()),
+            Ok(lifetime) => Err(spanned_error(
+                format!("Expected `'{expect}`"),
+                lifetime.apostrophe,
+            )),
+            Err(err) => {
+                self.next();
+                Err(err)
+            }
+        }
+    }
+
+    fn parse<T: Parse>(&mut self) -> Result<T, TokenStream> {
+        T::parse(self)
+    }
+
+    fn parse_all<T, F>(&mut self, parser: F) -> Result<T, TokenStream>
+    where
+        F: FnOnce(&mut TokenIter) -> Result<T, TokenStream>,
+    {
+        let value = parser(self)?;
+        self.expect_end()?;
+
+        Ok(value)
+    }
+
+    fn expect_end(&mut self) -> Result<(), TokenStream> {
+        match self.peek() {
+            None => Ok(()),
+            tree => Err(spanned_error("Unexpected token", tree.cloned().as_span())),
+        }
+    }
+
+    fn peek_is<T: Peek>(&self, token: T) -> bool {
+        token.peek(self)
+    }
+
+    fn lookahead(&self) -> Lookahead {
+        Lookahead::new(self)
+    }
+
+    fn peek_keyword<'k>(&self, keywords: &[&'k str]) -> Option<&'k str> {
+        match self.peek() {
+            Some(TokenTree::Ident(ident)) => {
+                // Raw identifiers are displayed with their `r#` prefix, so they never match.
+                let name = ident.to_string();
+                keywords.iter().copied().find(|keyword| *keyword == name)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_one_of(&mut self, keywords: &[&str]) -> Result<Ident, TokenStream> {
+        if self.peek_keyword(keywords).is_some() {
+            return self.try_ident();
+        }
+
+        let list = keywords
+            .iter()
+            .map(|keyword| format!("`{keyword}`"))
+            .collect::<Vec<_>>();
+        let message = match list.as_slice() {
+            [keyword] => format!("Expected {keyword}"),
+            _ => format!("Expected one of {}", list.join(", ")),
+        };
+
+        Err(spanned_error(message, self.next().as_span()))
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        let last = op.chars().count().saturating_sub(1);
+
+        !op.is_empty()
+            && op.chars().enumerate().all(|(i, ch)| match self.peek_n(i) {
+                Some(TokenTree::Punct(punct)) => {
+                    punct.as_char() == ch && (i == last || punct.spacing() == Spacing::Joint)
+                }
+                _ => false,
+            })
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<Span, TokenStream> {
+        let span = self.peek().cloned().as_span();
+        if !self.peek_op(op) {
+            self.next();
+            return Err(spanned_error(format!("Expected `{op}`"), span));
+        }
+
+        for _ in op.chars() {
+            self.next();
+        }
+
+        Ok(span)
+    }
+
+    fn try_group(&mut self) -> Result<Group, TokenStream> {
+        match self.next_if(|token| matches!(token, TokenTree::Group(_))) {
+            Some(TokenTree::Group(group)) => Ok(group),
+            tree => Err(spanned_error("Expected group", tree.as_span())),
+        }
+    }
+
+    fn try_ident(&mut self) -> Result<Ident, TokenStream> {
+        match self.next_if(|token| matches!(token, TokenTree::Ident(_))) {
+            Some(TokenTree::Ident(ident)) => Ok(ident),
+            tree => Err(spanned_error("Expected identifier", tree.as_span())),
+        }
+    }
+
+    fn try_lit(&mut self) -> Result<Literal, TokenStream> {
+        match self.next_if(|token| matches!(token, TokenTree::Literal(_))) {
+            Some(TokenTree::Literal(lit)) => Ok(lit),
+            tree => Err(spanned_error("Expected literal", tree.as_span())),
+        }
+    }
+
+    fn try_punct(&mut self) -> Result<Punct, TokenStream> {
+        match self.next_if(|token| matches!(token, TokenTree::Punct(_))) {
+            Some(TokenTree::Punct(punct)) => Ok(punct),
+            tree => Err(spanned_error("Expected punctuation", tree.as_span())),
+        }
+    }
+
+    fn try_lifetime(&mut self) -> Result<Lifetime, TokenStream> {
+        match (self.peek(), self.peek_n(1)) {
+            (Some(TokenTree::Punct(punct)), Some(TokenTree::Ident(_)))
+                if punct.as_char() == '\'' && punct.spacing() == Spacing::Joint =>
+            {
+                let apostrophe = self.try_punct()?.span();
+                let ident = self.try_ident()?;
+
+                Ok(Lifetime { apostrophe, ident })
+            }
+            (tree, _) => Err(spanned_error("Expected lifetime", tree.cloned().as_span())),
+        }
+    }
+
+    fn parse_punctuated<T, F>(
+        &mut self,
+        sep: char,
+        mut parser: F,
+    ) -> Result<Punctuated<T>, TokenStream>
+    where
+        F: FnMut(&mut TokenIter) -> Result<T, TokenStream>,
+    {
+        let mut punctuated = Punctuated {
+            items: vec![],
+            separators: vec![],
+        };
+
+        while self.peek().is_some() {
+            punctuated.items.push(parser(self)?);
+
+            match self.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == sep => {
+                    punctuated.separators.push(punct.span());
+                }
+                None => break,
+                tree => return Err(spanned_error(format!("Expected `{sep}`"), tree.as_span())),
+            }
+        }
+
+        Ok(punctuated)
+    }
+}
+
+impl ToTokens for Lifetime {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let mut apostrophe = Punct::new('\'', Spacing::Joint);
+        apostrophe.set_span(self.apostrophe);
+        tokens.extend([TokenTree::Punct(apostrophe)]);
+        self.ident.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for PunctSeq {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        push_punct(tokens, self.span, self.op);
+    }
+}
+
+impl Parse for Attribute {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        // Leave the input untouched on a mismatch, so `Option<Attribute>` can backtrack.
+        if !iter.peek_is(token::Op("#")) {
+            return Err(spanned_error(
+                "Expected `#`",
+                iter.peek().cloned().as_span(),
+            ));
+        }
+        iter.expect_punct('#')?;
+        let mut group = iter.expect_group(Delimiter::Bracket)?;
+        let name = group.try_ident()?;
+
+        Ok(Self {
+            name,
+            tree: group.collect::<TokenStream>().into_token_iter(),
+            style: AttrStyle::Outer,
+        })
+    }
+}
+
+impl Parse for Path {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        let (path, span) = iter.parse_path()?;
+
+        Ok(Self { path, span })
+    }
+}
+
+impl Parse for Type {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        let tokens = iter.parse_type()?;
+
+        Ok(Self { tokens })
+    }
+}
+
+impl Parse for Visibility {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_visibility_full()
+    }
+}
+
+impl Parse for Lifetime {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.try_lifetime()
+    }
+}
+
+impl Parse for Generics {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_generics()
+    }
+}
+
+impl Parse for Signature {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_signature()
+    }
+}
+
+impl Parse for Union {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_union()
+    }
+}
+
+impl Parse for ItemFn {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_fn()
+    }
+}
+
+impl Parse for ItemImpl {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_impl()
+    }
+}
+
+impl Parse for ItemTrait {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_trait()
+    }
+}
+
+impl Parse for Item {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_item()
+    }
+}
+
+impl Parse for CfgPredicate {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_cfg_predicate()
+    }
+}
+
+impl Parse for Expr {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_expr()
+    }
+}
+
+impl ToTokens for Expr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.tokens.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Type {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.tokens.to_tokens(tokens);
+    }
+}
+
+/// The path is re-lexed from its string form, and every token gets the span of the first token.
+impl ToTokens for Path {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self.path.parse::<TokenStream>() {
+            Ok(stream) => tokens.extend(respan(stream, self.span)),
+            Err(_) => tokens.extend(spanned_error("Invalid path", self.span)),
+        }
+    }
+}
+
+impl ToTokens for Attribute {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let span = self.name.span();
+        let spacing = match self.style {
+            AttrStyle::Outer => Spacing::Alone,
+            AttrStyle::Inner => Spacing::Joint,
+        };
+        let mut pound = Punct::new('#', spacing);
+        pound.set_span(span);
+        tokens.extend([TokenTree::Punct(pound)]);
+        if self.style == AttrStyle::Inner {
+            let mut bang = Punct::new('!', Spacing::Alone);
+            bang.set_span(span);
+            tokens.extend([TokenTree::Punct(bang)]);
+        }
+
+        let mut inner = TokenStream::new();
+        self.name.to_tokens(&mut inner);
+        inner.extend(self.tree.clone());
+        let mut group = Group::new(Delimiter::Bracket, inner);
+        group.set_span(span);
+
+        tokens.extend([TokenTree::Group(group)]);
+    }
+}
+
+impl ToTokens for Visibility {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Inherited => (),
+            Self::Public(ident) => ident.to_tokens(tokens),
+            Self::Restricted(ident, group) => {
+                ident.to_tokens(tokens);
+                group.to_tokens(tokens);
+            }
+        }
+    }
+}
+
+impl ToTokens for GenericParam {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.name_to_tokens(tokens);
+        self.bounds_to_tokens(tokens);
+        if let Some(default) = &self.default {
+            let eq = self.eq.unwrap_or_else(|| self.name.span());
+            tokens.extend([spanned_punct('=', eq)]);
+            default.to_tokens(tokens);
+        }
+    }
+}
+
+impl ToTokens for Generics {
+    /// Emits the parameters as declared, e.g. `<T: Clone = String>`.
+    ///
+    /// The where clause is not included. See [`Generics::where_clause`].
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.params_to_tokens(GenericParam::to_tokens));
+    }
+}
+
+impl ToTokens for Field {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        tokens.extend([spanned_punct(':', self.colon)]);
+        self.ty.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Union {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        Ident::new("union", self.name.span()).to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        self.generics.to_tokens(tokens);
+        self.generics.where_clause().to_tokens(tokens);
+
+        let mut fields = TokenStream::new();
+        for field in &self.fields {
+            field.to_tokens(&mut fields);
+            fields.extend([spanned_punct(',', last_span(&field.ty))]);
+        }
+        let mut group = Group::new(Delimiter::Brace, fields);
+        group.set_span(self.span);
+        group.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ItemFn {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        self.defaultness.to_tokens(tokens);
+        self.sig.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Signature {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.constness.to_tokens(tokens);
+        self.asyncness.to_tokens(tokens);
+        self.unsafety.to_tokens(tokens);
+        if let Some((extern_token, abi)) = &self.abi {
+            extern_token.to_tokens(tokens);
+            abi.to_tokens(tokens);
+        }
+        self.fn_token.to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        self.generics.to_tokens(tokens);
+
+        let mut inputs = TokenStream::new();
+        punctuated_to_tokens(&self.inputs, ',', &mut inputs);
+        let mut group = Group::new(Delimiter::Parenthesis, inputs);
+        group.set_span(self.paren);
+        group.to_tokens(tokens);
+
+        if let Some((arrow, output)) = &self.output {
+            PunctSeq::new_spanned("->", *arrow).to_tokens(tokens);
+            output.to_tokens(tokens);
+        }
+        self.generics.where_clause().to_tokens(tokens);
+    }
+}
+
+impl ToTokens for FnArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Receiver(receiver) => receiver.to_tokens(tokens),
+            Self::Typed(pat_type) => pat_type.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for Receiver {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        if let Some((and, lifetime)) = &self.reference {
+            and.to_tokens(tokens);
+            lifetime.to_tokens(tokens);
+        }
+        self.mutability.to_tokens(tokens);
+        self.self_token.to_tokens(tokens);
+        if let (Some(colon), Some(ty)) = (self.colon, &self.ty) {
+            tokens.extend([spanned_punct(':', colon)]);
+            ty.to_tokens(tokens);
+        }
+    }
+}
+
+impl ToTokens for PatType {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.pat.to_tokens(tokens);
+        tokens.extend([spanned_punct(':', self.colon)]);
+        self.ty.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Pat {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Ident(pat) => pat.to_tokens(tokens),
+            Self::Lit(neg, lit) => {
+                neg.to_tokens(tokens);
+                lit.to_tokens(tokens);
+            }
+            Self::Path(path) => path.to_tokens(tokens),
+            Self::Reference(pat) => {
+                pat.and.to_tokens(tokens);
+                pat.mutability.to_tokens(tokens);
+                pat.pat.to_tokens(tokens);
+            }
+            Self::Rest(span) => PunctSeq::new_spanned("..", *span).to_tokens(tokens),
+            Self::Slice(span, elems) => {
+                tokens.extend([pat_list_to_tokens(*span, Delimiter::Bracket, elems)]);
+            }
+            Self::Struct(pat) => {
+                pat.path.to_tokens(tokens);
+
+                let mut fields = TokenStream::new();
+                punctuated_to_tokens(&pat.fields, ',', &mut fields);
+                if let Some(span) = pat.rest {
+                    PunctSeq::new_spanned("..", span).to_tokens(&mut fields);
+                }
+
+                let mut group = Group::new(Delimiter::Brace, fields);
+                group.set_span(pat.span);
+                group.to_tokens(tokens);
+            }
+            Self::Tuple(span, elems) => {
+                tokens.extend([pat_list_to_tokens(*span, Delimiter::Parenthesis, elems)]);
+            }
+            Self::TupleStruct(pat) => {
+                pat.path.to_tokens(tokens);
+                tokens.extend([pat_list_to_tokens(
+                    pat.span,
+                    Delimiter::Parenthesis,
+                    &pat.elems,
+                )]);
+            }
+            Self::Wild(ident) => ident.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for PatIdent {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.by_ref.to_tokens(tokens);
+        self.mutability.to_tokens(tokens);
+        self.ident.to_tokens(tokens);
+        if let Some((at, subpat)) = &self.subpat {
+            tokens.extend([spanned_punct('@', *at)]);
+            subpat.to_tokens(tokens);
+        }
+    }
+}
+
+impl ToTokens for FieldPat {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(colon) = self.colon {
+            self.member.to_tokens(tokens);
+            tokens.extend([spanned_punct(':', colon)]);
+        }
+        self.pat.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Member {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Named(ident) => ident.to_tokens(tokens),
+            Self::Unnamed(lit) => lit.to_tokens(tokens),
+        }
+    }
+}
+
+impl Parse for Pat {
+    fn parse(iter: &mut TokenIter) -> Result<Self, TokenStream> {
+        iter.parse_pat()
+    }
+}
+
+impl ToTokens for ItemImpl {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        attrs_to_tokens(&self.attrs, AttrStyle::Outer, tokens);
+        self.unsafety.to_tokens(tokens);
+        self.impl_token.to_tokens(tokens);
+        self.generics.impl_generics().to_tokens(tokens);
+        if let Some((negative, path, for_token)) = &self.trait_ {
+            negative.to_tokens(tokens);
+            path.to_tokens(tokens);
+            for_token.to_tokens(tokens);
+        }
+        self.self_ty.to_tokens(tokens);
+        self.generics.where_clause().to_tokens(tokens);
+
+        let mut items = TokenStream::new();
+        attrs_to_tokens(&self.attrs, AttrStyle::Inner, &mut items);
+        for item in &self.items {
+            item.to_tokens(&mut items);
+        }
+        let mut group = Group::new(Delimiter::Brace, items);
+        group.set_span(self.span);
+        group.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ImplItem {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Fn(item) => item.to_tokens(tokens),
+            Self::Const(item) | Self::Type(item) | Self::Macro(item) => item.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for Item {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Struct(item)
+            | Self::Enum(item)
+            | Self::Mod(item)
+            | Self::Const(item)
+            | Self::Static(item)
+            | Self::Type(item)
+            | Self::ExternCrate(item)
+            | Self::Macro(item) => item.to_tokens(tokens),
+            Self::Use(item) => item.to_tokens(tokens),
+            Self::ForeignMod(item) => item.to_tokens(tokens),
+            Self::Union(item) => item.to_tokens(tokens),
+            Self::Fn(item) => item.to_tokens(tokens),
+            Self::Impl(item) => item.to_tokens(tokens),
+            Self::Trait(item) => item.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for ItemTrait {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        attrs_to_tokens(&self.attrs, AttrStyle::Outer, tokens);
+        self.vis.to_tokens(tokens);
+        self.unsafety.to_tokens(tokens);
+        self.trait_token.to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        self.generics.to_tokens(tokens);
+        if let Some(colon) = self.colon {
+            tokens.extend([spanned_punct(':', colon)]);
+            self.supertraits.to_tokens(tokens);
+        }
+        self.generics.where_clause().to_tokens(tokens);
+
+        let mut items = TokenStream::new();
+        attrs_to_tokens(&self.attrs, AttrStyle::Inner, &mut items);
+        for item in &self.items {
+            item.to_tokens(&mut items);
+        }
+        let mut group = Group::new(Delimiter::Brace, items);
+        group.set_span(self.span);
+        group.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for TraitItem {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Fn(item) => item.to_tokens(tokens),
+            Self::Type(item) => item.to_tokens(tokens),
+            Self::Const(item) => item.to_tokens(tokens),
+            Self::Macro(item) => item.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for TraitItemFn {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.sig.to_tokens(tokens);
+        self.default.to_tokens(tokens);
+        if let Some(semi) = self.semi {
+            tokens.extend([spanned_punct(';', semi)]);
+        }
+    }
+}
+
+impl ToTokens for TraitItemType {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.type_token.to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        self.generics.to_tokens(tokens);
+        if let Some(colon) = self.colon {
+            tokens.extend([spanned_punct(':', colon)]);
+            self.bounds.to_tokens(tokens);
+        }
+        if let (Some(eq), Some(default)) = (self.eq, &self.default) {
+            tokens.extend([spanned_punct('=', eq)]);
+            default.to_tokens(tokens);
+        }
+        self.generics.where_clause().to_tokens(tokens);
+        tokens.extend([spanned_punct(';', self.semi)]);
+    }
+}
+
+impl ToTokens for TraitItemConst {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.const_token.to_tokens(tokens);
+        self.name.to_tokens(tokens);
+        tokens.extend([spanned_punct(':', self.colon)]);
+        self.ty.to_tokens(tokens);
+        if let (Some(eq), Some(default)) = (self.eq, &self.default) {
+            tokens.extend([spanned_punct('=', eq)]);
+            default.to_tokens(tokens);
+        }
+        tokens.extend([spanned_punct(';', self.semi)]);
+    }
+}
+
+impl ToTokens for RawItem {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        self.tokens.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ItemUse {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        self.use_token.to_tokens(tokens);
+        self.tree.to_tokens(tokens);
+        tokens.extend([spanned_punct(';', self.semi)]);
+    }
+}
+
+impl ToTokens for ItemForeignMod {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attrs.to_tokens(tokens);
+        self.unsafety.to_tokens(tokens);
+        self.abi.0.to_tokens(tokens);
+        self.abi.1.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for CfgPredicate {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let (name, predicates) = match self {
+            Self::Option(ident) => {
+                ident.to_tokens(tokens);
+                return;
+            }
+            Self::KeyValue(ident, lit) => {
+                ident.to_tokens(tokens);
+                tokens.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+                lit.to_tokens(tokens);
+                return;
+            }
+            Self::All(predicates) => ("all", predicates.as_slice()),
+            Self::Any(predicates) => ("any", predicates.as_slice()),
+            Self::Not(predicate) => ("not", std::slice::from_ref(predicate.as_ref())),
+        };
+
+        let mut inner = TokenStream::new();
+        for (i, predicate) in predicates.iter().enumerate() {
+            if i > 0 {
+                inner.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+            }
+            predicate.to_tokens(&mut inner);
+        }
+
+        Ident::new(name, Span::call_site()).to_tokens(tokens);
+        Group::new(Delimiter::Parenthesis, inner).to_tokens(tokens);
+    }
+}
+
+impl ToTokens for CfgAttr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let mut args = self.predicate.to_token_stream();
+        for attr in &self.attrs {
+            args.extend([punct(',', Spacing::Alone)]);
+            attr.name.to_tokens(&mut args);
+            args.extend(attr.tree.clone());
+        }
+        let mut group = Group::new(Delimiter::Parenthesis, args);
+        group.set_span(self.span);
+
+        let attr = Attribute {
+            name: Ident::new("cfg_attr", self.span),
+            tree: TokenStream::from(TokenTree::Group(group)).into_token_iter(),
+            style: self.style,
+        };
+        attr.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Repr {
+    /// Emits a single `#[repr(...)]` attribute, or nothing for the default representation.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let mut hints = TokenStream::new();
+        let mut first_span = None;
+        let mut hint = |name: &str, span: Span, arg: Option<u32>| {
+            if first_span.is_some() {
+                hints.extend([punct(',', Spacing::Alone)]);
+            }
+            first_span.get_or_insert(span);
+            Ident::new(name, span).to_tokens(&mut hints);
+            if let Some(arg) = arg {
+                let mut lit = Literal::u32_unsuffixed(arg);
+                lit.set_span(span);
+                let mut group = Group::new(Delimiter::Parenthesis, lit.to_token_stream());
+                group.set_span(span);
+                group.to_tokens(&mut hints);
+            }
+        };
+
+        if let Some((int, span)) = self.int {
+            hint(int.name(), span, None);
+        }
+        if let Some(span) = self.c {
+            hint("C", span, None);
+        }
+        if let Some(span) = self.transparent {
+            hint("transparent", span, None);
+        }
+        if let Some((align, span)) = self.packed {
+            hint("packed", span, if align == 1 { None } else { Some(align) });
+        }
+        if let Some((align, span)) = self.align {
+            hint("align", span, Some(align));
+        }
+
+        let span = match first_span {
+            Some(span) => span,
+            None => return,
+        };
+        let mut inner = TokenStream::new();
+        Ident::new("repr", span).to_tokens(&mut inner);
+        let mut group = Group::new(Delimiter::Parenthesis, hints);
+        group.set_span(span);
+        group.to_tokens(&mut inner);
+
+        outer_attr(span, inner, tokens);
+    }
+}
+
+impl ToTokens for DocComment {
+    /// Emits one `#[doc = "..."]` attribute per line, followed by the macro doc attributes.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for line in &self.lines {
+            let mut inner = TokenStream::new();
+            Ident::new("doc", line.span).to_tokens(&mut inner);
+            inner.extend([punct('=', Spacing::Alone)]);
+            let mut lit = Literal::string(&line.text);
+            lit.set_span(line.span);
+            lit.to_tokens(&mut inner);
+
+            outer_attr(line.span, inner, tokens);
+        }
+
+        for tokens_macro in &self.macros {
+            let span = tokens_macro.clone().into_iter().next().as_span();
+            let mut inner = TokenStream::new();
+            Ident::new("doc", span).to_tokens(&mut inner);
+            inner.extend([punct('=', Spacing::Alone)]);
+            inner.extend(tokens_macro.clone());
+
+            outer_attr(span, inner, tokens);
+        }
+    }
+}
+
+impl ToTokens for IntType {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        Ident::new(self.name(), Span::call_site()).to_tokens(tokens);
+    }
+}
+
+/// Create a punctuation token tree.
+fn punct(ch: char, spacing: Spacing) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, spacing))
+}
+
+/// Expect the given punctuation, returning its span.
+fn expect_punct_span(iter: &mut TokenIter, ch: char) -> Result<Span, TokenStream> {
+    let span = iter.peek().cloned().as_span();
+    iter.expect_punct(ch)?;
+
+    Ok(span)
+}
+
+/// Create an alone punctuation token tree with the given span.
+fn spanned_punct(ch: char, span: Span) -> TokenTree {
+    let mut punct = Punct::new(ch, Spacing::Alone);
+    punct.set_span(span);
+
+    TokenTree::Punct(punct)
+}
+
+/// The span of the first token, or the call site when there are no tokens.
+fn first_span(tokens: &TokenStream) -> Span {
+    tokens.clone().into_iter().next().as_span()
+}
+
+/// The span of the last token, or the call site when there are no tokens.
+fn last_span(tokens: &TokenStream) -> Span {
+    tokens.clone().into_iter().last().as_span()
+}
+
+/// Parse a group with the given delimiter, returning its span and an iterator over its contents.
+fn expect_delimited(
+    iter: &mut TokenIter,
+    delimiter: Delimiter,
+) -> Result<(Span, TokenIter), TokenStream> {
+    let span = iter.peek().cloned().as_span();
+    let inner = iter.expect_group(delimiter)?;
+
+    Ok((span, inner))
+}
+
+/// Set the span of every token in `stream`, including tokens inside groups.
+fn respan(stream: TokenStream, span: Span) -> TokenStream {
+    stream
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Group(group) => {
+                let mut group = Group::new(group.delimiter(), respan(group.stream(), span));
+                group.set_span(span);
+                TokenTree::Group(group)
+            }
+            mut tree => {
+                tree.set_span(span);
+                tree
+            }
+        })
+        .collect()
+}
+
+/// Emit the attributes with the given style.
+fn attrs_to_tokens(attrs: &[Attribute], style: AttrStyle, tokens: &mut TokenStream) {
+    for attr in attrs.iter().filter(|attr| attr.style == style) {
+        attr.to_tokens(tokens);
+    }
+}
+
+/// Emit `#[...]` around the given attribute tokens.
+fn outer_attr(span: Span, inner: TokenStream, tokens: &mut TokenStream) {
+    let mut pound = Punct::new('#', Spacing::Alone);
+    pound.set_span(span);
+    let mut group = Group::new(Delimiter::Bracket, inner);
+    group.set_span(span);
+
+    tokens.extend([TokenTree::Punct(pound), TokenTree::Group(group)]);
+}
+
+/// Consume the next token if it is the given punctuation, returning its span.
+fn take_punct(iter: &mut TokenIter, ch: char) -> Option<Span> {
+    iter.next_if(|tree| matches!(tree, TokenTree::Punct(punct) if punct.as_char() == ch))
+        .map(|tree| tree.span())
+}
+
+/// Consume the next token if it is the given keyword.
+fn take_keyword(iter: &mut TokenIter, keyword: &str) -> Option<Ident> {
+    iter.peek_keyword(&[keyword])?;
+    iter.try_ident().ok()
+}
+
+/// Parse a parenthesized or bracketed list of patterns.
+fn parse_pat_list(iter: &mut TokenIter) -> Result<(Span, Punctuated<Pat>), TokenStream> {
+    let group = iter.try_group()?;
+    let elems = group
+        .stream()
+        .into_token_iter()
+        .parse_punctuated(',', TokenIterExt::parse_pat)?;
+
+    Ok((group.span(), elems))
+}
+
+/// Parse the path of a pattern, returning the number of segments.
+///
+/// Qualified paths like `<T>::X` and turbofish arguments like `Wrapper::<u8>` are supported.
+fn parse_pat_path(iter: &mut TokenIter) -> Result<(TokenStream, usize), TokenStream> {
+    let mut path = TokenStream::new();
+    let mut segments = 0;
+
+    // A qualified path, e.g. `<T>::X` or `<T as Trait>::X`.
+    if iter.peek_op("<") {
+        collect_generic_args(iter, &mut path)?;
+        segments += 1;
+        if !iter.peek_op("::") {
+            return Err(spanned_error(
+                "Expected `::`",
+                iter.peek().cloned().as_span(),
+            ));
+        }
+    }
+
+    loop {
+        if iter.peek_op("::") {
+            let span = iter.expect_op("::")?;
+            PunctSeq::new_spanned("::", span).to_tokens(&mut path);
+        }
+        path.extend([TokenTree::Ident(iter.try_ident()?)]);
+        segments += 1;
+
+        // A turbofish, e.g. `Wrapper::<u8>`.
+        if iter.peek_op("::")
+            && matches!(iter.peek_n(2), Some(TokenTree::Punct(punct)) if punct.as_char() == '<')
+        {
+            let span = iter.expect_op("::")?;
+            PunctSeq::new_spanned("::", span).to_tokens(&mut path);
+            collect_generic_args(iter, &mut path)?;
+        }
+
+        if !iter.peek_op("::") {
+            break;
+        }
+    }
+
+    Ok((path, segments))
+}
+
+/// Parse an identifier binding with optional `ref`, `mut`, and `@` subpattern.
+fn parse_pat_ident(iter: &mut TokenIter) -> Result<PatIdent, TokenStream> {
+    let by_ref = take_keyword(iter, "ref");
+    let mutability = take_keyword(iter, "mut");
+    let ident = iter.try_ident()?;
+    let subpat = parse_subpat(iter)?;
+
+    Ok(PatIdent {
+        by_ref,
+        mutability,
+        ident,
+        subpat,
+    })
+}
+
+/// Parse the pattern following `@` in a binding, if any.
+fn parse_subpat(iter: &mut TokenIter) -> Result<Option<(Span, Box<Pat>)>, TokenStream> {
+    if iter.peek_op("@") {
+        let at = iter.expect_op("@")?;
+        Ok(Some((at, Box::new(iter.parse_pat()?))))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parse a field in a struct pattern, e.g. `y: 0` or the shorthand `ref x`.
+fn parse_field_pat(iter: &mut TokenIter) -> Result<FieldPat, TokenStream> {
+    // Tuple fields are never shorthand, e.g. `0: a`.
+    if let Some(TokenTree::Literal(_)) = iter.peek() {
+        let member = Member::Unnamed(iter.try_lit()?);
+        let colon = Some(expect_punct_span(iter, ':')?);
+        let pat = iter.parse_pat()?;
+
+        return Ok(FieldPat { member, colon, pat });
+    }
+
+    let mut lookahead = iter.fork();
+    lookahead.try_ident()?;
+    if lookahead.peek_op(":") && !lookahead.peek_op("::") {
+        let member = Member::Named(iter.try_ident()?);
+        let colon = Some(expect_punct_span(iter, ':')?);
+        let pat = iter.parse_pat()?;
+
+        return Ok(FieldPat { member, colon, pat });
+    }
+
+    let binding = parse_pat_ident(iter)?;
+    Ok(FieldPat {
+        member: Member::Named(binding.ident.clone()),
+        colon: None,
+        pat: Pat::Ident(binding),
+    })
+}
+
+/// Collect generic arguments in angle brackets, e.g. `<u8>` in `Wrapper::<u8>`.
+fn collect_generic_args(iter: &mut TokenIter, tokens: &mut TokenStream) -> Result<(), TokenStream> {
+    let span = iter.peek().cloned().as_span();
+    let mut nesting = 0_usize;
+    let mut arrow = false;
+
+    for tree in iter.by_ref() {
+        if let TokenTree::Punct(punct) = &tree {
+            let ch = punct.as_char();
+            if ch == '<' {
+                nesting += 1;
+            } else if ch == '>' && !arrow {
+                nesting -= 1;
+            }
+            arrow = ch == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        tokens.extend([tree]);
+
+        if nesting == 0 {
+            return Ok(());
+        }
+    }
+
+    Err(spanned_error("Unclosed `<`", span))
+}
+
+/// Emit the items of a punctuated list, keeping the separator spans.
+fn punctuated_to_tokens<T: ToTokens>(list: &Punctuated<T>, sep: char, tokens: &mut TokenStream) {
+    for (i, item) in list.items.iter().enumerate() {
+        item.to_tokens(tokens);
+        if let Some(span) = list.separators.get(i) {
+            tokens.extend([spanned_punct(sep, *span)]);
+        }
+    }
+}
+
+/// Emit a comma-separated list, keeping the separator spans.
+fn pat_list_to_tokens(span: Span, delimiter: Delimiter, elems: &Punctuated<Pat>) -> TokenTree {
+    let mut inner = TokenStream::new();
+    punctuated_to_tokens(elems, ',', &mut inner);
+
+    let mut group = Group::new(delimiter, inner);
+    group.set_span(span);
+
+    TokenTree::Group(group)
+}
+
+/// Keywords which start or separate expressions, so they are not operands.
+const EXPR_KEYWORDS: &[&str] = &[
+    "as", "async", "box", "break", "const", "else", "for", "if", "in", "let", "loop", "match",
+    "move", "mut", "ref", "return", "static", "unsafe", "while", "yield",
+];
+
+/// Classify an expression by its top-level tokens.
+fn classify_expr(tokens: &TokenStream) -> ExprKind {
+    let trees = tokens.clone().into_iter().collect::<Vec<_>>();
+    match trees.as_slice() {
+        [TokenTree::Literal(_)] => return ExprKind::Lit,
+        [TokenTree::Ident(ident)] if ["true", "false"].contains(&ident.to_string().as_str()) => {
+            return ExprKind::Lit;
+        }
+        [TokenTree::Group(group)] if group.delimiter() == Delimiter::Brace => {
+            return ExprKind::Block;
+        }
+        [TokenTree::Ident(ident), ..] if EXPR_KEYWORDS.contains(&ident.to_string().as_str()) => {
+            return ExprKind::Other;
+        }
+        [TokenTree::Punct(punct), ..] if punct.as_char() == '|' => return ExprKind::Other,
+        _ => (),
+    }
+
+    // Look for a binary operator following an operand.
+    let mut iter = tokens.clone().into_token_iter();
+    let mut nesting = 0_usize;
+    let mut operand = false;
+    let mut path_sep = false;
+    let mut cast = false;
+    while let Some(tree) = iter.peek() {
+        let mut after_path_sep = false;
+
+        match tree {
+            TokenTree::Punct(_) if iter.peek_op("::") => {
+                iter.next();
+                after_path_sep = true;
+                operand = false;
+            }
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+
+                if ch == '<' && (nesting > 0 || path_sep || !operand) {
+                    nesting += 1;
+                    operand = false;
+                } else if ch == '>' && nesting > 0 {
+                    nesting -= 1;
+                    operand = true;
+                } else if nesting > 0 {
+                    // Operators inside turbofish arguments are not binary operations.
+                } else if ch == '?' {
+                    operand = true;
+                } else if ch == '|' && !operand {
+                    // A closure body extends to the end, e.g. `&|a| a + 1` is a reference.
+                    break;
+                } else if (ch == '.' && !iter.peek_op("..")) || (ch == '!' && !iter.peek_op("!=")) {
+                    // Member access, a macro `!`, or a unary `!`.
+                    operand = false;
+                } else if operand {
+                    return ExprKind::Binary;
+                }
+            }
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                cast |= nesting == 0 && name == "as";
+                operand = name != "as";
+            }
+            TokenTree::Group(_) | TokenTree::Literal(_) => operand = true,
+        }
+
+        path_sep = after_path_sep;
+        iter.next();
+    }
+
+    let len = trees.len();
+    match trees.as_slice() {
+        [TokenTree::Punct(punct), ..] if "-!*&".contains(punct.as_char()) => ExprKind::Unary,
+        _ if cast => ExprKind::Other,
+        [callee @ .., TokenTree::Punct(bang), TokenTree::Group(_)]
+            if bang.as_char() == '!' && is_path(callee) =>
+        {
+            ExprKind::Macro
+        }
+        // A lone `(...)` group is a tuple or a parenthesized expression, not a call.
+        [callee @ .., TokenTree::Group(group)]
+            if group.delimiter() == Delimiter::Parenthesis && !callee.is_empty() =>
+        {
+            if is_path(callee) {
+                return ExprKind::Call;
+            }
+
+            // A method call is `receiver.method(...)` or `receiver.method::<T>(...)`.
+            let dot = callee.iter().rposition(
+                |tree| matches!(tree, TokenTree::Punct(punct) if punct.as_char() == '.'),
+            );
+            match dot.map(|dot| &callee[dot + 1..]) {
+                Some([TokenTree::Ident(_)]) => ExprKind::MethodCall,
+                Some([TokenTree::Ident(_), TokenTree::Punct(colon), ..])
+                    if colon.as_char() == ':' =>
+                {
+                    ExprKind::MethodCall
+                }
+                _ => ExprKind::Call,
+            }
+        }
+        _ if len > 0 && is_path(&trees) => ExprKind::Path,
+        _ => ExprKind::Other,
+    }
+}
+
+/// Check if the tokens form a path, e.g. `foo`, `foo::Bar`, or `Vec::<u8>::new`.
+fn is_path(trees: &[TokenTree]) -> bool {
+    let mut nesting = 0_usize;
+    let all = trees.iter().all(|tree| match tree {
+        TokenTree::Punct(punct) if punct.as_char() == '<' => {
+            nesting += 1;
+            true
+        }
+        TokenTree::Punct(punct) if punct.as_char() == '>' && nesting > 0 => {
+            nesting -= 1;
+            true
+        }
+        _ if nesting > 0 => true,
+        TokenTree::Ident(_) => true,
+        TokenTree::Punct(punct) => punct.as_char() == ':',
+        _ => false,
+    });
+
+    !trees.is_empty() && all && nesting == 0
+}
+
+/// Parse a single function argument.
+fn parse_fn_arg(iter: &mut TokenIter) -> Result<FnArg, TokenStream> {
+    let attrs = iter.parse_attributes()?;
+
+    // Look ahead for `self`, `mut self`, `&self`, `&mut self`, or `&'a mut self`.
+    let mut lookahead = iter.fork();
+    if let Some(TokenTree::Punct(punct)) = lookahead.peek() {
+        if punct.as_char() == '&' {
+            lookahead.next();
+        }
+    }
+    lookahead.try_lifetime().ok();
+    take_keyword(&mut lookahead, "mut");
+
+    if take_keyword(&mut lookahead, "self").is_some() {
+        let reference = match iter.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '&' => {
+                let and = iter.try_punct()?;
+                Some((and, iter.try_lifetime().ok()))
+            }
+            _ => None,
+        };
+        let mutability = take_keyword(iter, "mut");
+        let self_token = iter.try_ident()?;
+        let colon = take_punct(iter, ':');
+        let ty = match colon {
+            Some(_) => Some(iter.parse_type()?),
+            None => None,
+        };
+
+        return Ok(FnArg::Receiver(Receiver {
+            attrs,
+            reference,
+            mutability,
+            self_token,
+            colon,
+            ty,
+        }));
+    }
+
+    let pat = iter.parse_pat()?;
+    let colon = expect_punct_span(iter, ':')?;
+    let ty = iter.parse_type()?;
+
+    Ok(FnArg::Typed(PatType {
+        attrs,
+        pat,
+        colon,
+        ty,
+    }))
+}
+
+/// Parse a single associated item in an `impl` block.
+fn parse_impl_item(iter: &mut TokenIter) -> Result<ImplItem, TokenStream> {
+    let attrs = iter.parse_attributes()?;
+    let vis = iter.parse_visibility_full()?;
+
+    // `default` is only a keyword when it qualifies an item, e.g. not in `default!();`.
+    let defaultness = match iter.peek_n(1) {
+        Some(TokenTree::Ident(_)) => take_keyword(iter, "default"),
+        _ => None,
+    };
+
+    if is_fn(iter) {
+        let sig = iter.parse_signature()?;
+        let body = match iter.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                iter.try_group()?
+            }
+            tree => return Err(spanned_error("Expected `{`", tree.cloned().as_span())),
+        };
+
+        return Ok(ImplItem::Fn(Box::new(ItemFn {
+            attrs,
+            vis,
+            defaultness,
+            sig,
+            body,
+        })));
+    }
+
+    let keyword = match iter.peek() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        tree => {
+            return Err(spanned_error(
+                "Expected associated item",
+                tree.cloned().as_span(),
+            ))
+        }
+    };
+    match keyword.as_str() {
+        "const" | "type" => {
+            let mut lookahead = iter.fork();
+            lookahead.next();
+            let name = lookahead.try_ident()?;
+            let mut tokens = defaultness.to_token_stream();
+            tokens.extend(collect_item(iter));
+            let item = RawItem {
+                attrs,
+                vis,
+                name,
+                tokens,
+            };
+
+            if keyword == "const" {
+                Ok(ImplItem::Const(item))
+            } else {
+                Ok(ImplItem::Type(item))
+            }
+        }
+        _ => parse_macro_item(iter, attrs, vis).map(ImplItem::Macro),
+    }
+}
+
+/// Parse a single associated item in a trait definition.
+fn parse_trait_item(iter: &mut TokenIter) -> Result<TraitItem, TokenStream> {
+    let attrs = iter.parse_attributes()?;
+
+    if is_fn(iter) {
+        let sig = iter.parse_signature()?;
+        let (default, semi) = match iter.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                (Some(iter.try_group()?), None)
+            }
+            _ => (None, Some(expect_punct_span(iter, ';')?)),
+        };
+
+        return Ok(TraitItem::Fn(Box::new(TraitItemFn {
+            attrs,
+            sig,
+            default,
+            semi,
+        })));
+    }
+
+    if let Some(type_token) = take_keyword(iter, "type") {
+        let name = iter.try_ident()?;
+        let mut generics = iter.parse_generics()?;
+        let colon = take_punct(iter, ':');
+        let bounds = match colon {
+            Some(_) => collect_until(iter, &[';', '='], &["where"]),
+            None => TokenStream::new(),
+        };
+        generics.where_clause = iter.parse_where_clause()?;
+        let eq = take_punct(iter, '=');
+        let default = match eq {
+            Some(_) => Some(iter.parse_type()?),
+            None => None,
+        };
+        generics.where_clause.extend(iter.parse_where_clause()?);
+        let semi = expect_punct_span(iter, ';')?;
+
+        return Ok(TraitItem::Type(TraitItemType {
+            attrs,
+            type_token,
+            name,
+            generics,
+            colon,
+            bounds,
+            eq,
+            default,
+            semi,
+        }));
+    }
+
+    if let Some(const_token) = take_keyword(iter, "const") {
+        let name = iter.try_ident()?;
+        let colon = expect_punct_span(iter, ':')?;
+        let ty = iter.parse_type()?;
+        let eq = take_punct(iter, '=');
+        let default = match eq {
+            Some(_) => Some(iter.parse_expr_tokens()?),
+            None => None,
+        };
+        let semi = expect_punct_span(iter, ';')?;
+
+        return Ok(TraitItem::Const(TraitItemConst {
+            attrs,
+            const_token,
+            name,
+            colon,
+            ty,
+            eq,
+            default,
+            semi,
+        }));
+    }
+
+    parse_macro_item(iter, attrs, Visibility::Inherited).map(TraitItem::Macro)
+}
+
+/// Look ahead for a function signature, skipping qualifiers.
+fn is_fn(iter: &TokenIter) -> bool {
+    let mut lookahead = iter.fork();
+    while let Some(TokenTree::Ident(ident)) = lookahead.next() {
+        match ident.to_string().as_str() {
+            "fn" => return true,
+            "const" | "async" | "unsafe" => (),
+            "extern" => {
+                lookahead.try_lit().ok();
+            }
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+/// Collect the tokens of an item up to and including the first `;` outside of any group.
+fn collect_item(iter: &mut TokenIter) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for tree in iter.by_ref() {
+        let end = matches!(&tree, TokenTree::Punct(punct) if punct.as_char() == ';');
+        tokens.extend([tree]);
+        if end {
+            break;
+        }
+    }
+
+    tokens
+}
+
+/// Collect the tokens of a struct, enum, or module definition.
+///
+/// Collection ends after the first `{...}` group or `;` outside of any group.
+fn collect_definition(iter: &mut TokenIter) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for tree in iter.by_ref() {
+        let end = match &tree {
+            TokenTree::Group(group) => group.delimiter() == Delimiter::Brace,
+            TokenTree::Punct(punct) => punct.as_char() == ';',
+            _ => false,
+        };
+        tokens.extend([tree]);
+        if end {
+            break;
+        }
+    }
+
+    tokens
+}
+
+/// Parse a macro invocation item, e.g. `foo!(...);`, `foo::bar! { ... }`, or `macro_rules! foo {}`.
+fn parse_macro_item(
+    iter: &mut TokenIter,
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+) -> Result<RawItem, TokenStream> {
+    let mut tokens = TokenStream::new();
+    let mut name = iter.try_ident()?;
+    tokens.extend([TokenTree::Ident(name.clone())]);
+
+    while iter.peek_op("::") {
+        let span = iter.expect_op("::")?;
+        PunctSeq::new_spanned("::", span).to_tokens(&mut tokens);
+        name = iter.try_ident()?;
+        tokens.extend([TokenTree::Ident(name.clone())]);
+    }
+    match iter.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '!' => {
+            tokens.extend([TokenTree::Punct(punct)]);
+        }
+        tree => return Err(spanned_error("Expected item", tree.as_span())),
+    }
+
+    // `macro_rules! name { ... }`
+    if let Some(TokenTree::Ident(_)) = iter.peek() {
+        tokens.extend(iter.next());
+    }
+
+    let group = iter.try_group()?;
+    let is_brace = group.delimiter() == Delimiter::Brace;
+    tokens.extend([TokenTree::Group(group)]);
+    if !is_brace {
+        let semi = iter.try_punct()?;
+        if semi.as_char() != ';' {
+            return Err(spanned_error("Expected `;`", semi.span()));
+        }
+        tokens.extend([TokenTree::Punct(semi)]);
+    }
+
+    Ok(RawItem {
+        attrs,
+        vis,
+        name,
+        tokens,
+    })
+}
+
+/// Collect tokens up to one of the `stop` punctuation characters or keywords.
+///
+/// Stops only match outside of `<...>` nesting, and the `>` in `->` is not counted. A `{...}`
+/// group outside of nesting also stops collection.
+fn collect_until(iter: &mut TokenIter, stop: &[char], keywords: &[&str]) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let mut nesting = 0_usize;
+    let mut arrow = false;
+
+    while let Some(tree) = iter.peek() {
+        match tree {
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+                let is_arrow = arrow && ch == '>';
+
+                if nesting == 0 && !is_arrow && stop.contains(&ch) {
+                    break;
+                }
+                if ch == '<' {
+                    nesting += 1;
+                } else if ch == '>' && !is_arrow {
+                    nesting = nesting.saturating_sub(1);
+                }
+                arrow = ch == '-' && punct.spacing() == Spacing::Joint;
+            }
+            TokenTree::Group(group) if nesting == 0 && group.delimiter() == Delimiter::Brace => {
+                break;
+            }
+            TokenTree::Ident(ident)
+                if nesting == 0 && keywords.contains(&ident.to_string().as_str()) =>
+            {
+                break
+            }
+            _ => arrow = false,
+        }
+
+        tokens.extend(iter.next());
+    }
+
+    tokens
+}
+
+impl std::fmt::Debug for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("Attribute")
+            .field("name", &self.name)
+            .field("tree", &"TokenIter {...}")
+            .field("style", &self.style)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_attribute_to_tokens() {
+        let mut input = TokenStream::from_str(r#"#[cfg(feature = "std")] #[doc = "Foo"]"#)
+            .unwrap()
+            .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+
+        assert_eq!(
+            attrs.to_token_stream().to_string(),
+            r#"# [cfg (feature = "std")] # [doc = "Foo"]"#
+        );
+    }
+
+    #[test]
+    fn test_tokeniter_parse_inner_attributes() {
+        let mut input =
+            TokenStream::from_str(r#"#![doc = "Foo"] #![allow(unused)] #[doc = "Bar"]"#)
+                .unwrap()
+                .into_token_iter();
+
+        let attrs = input.parse_inner_attributes().unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].name.to_string(), "doc");
+        assert_eq!(attrs[0].style, AttrStyle::Inner);
+        assert_eq!(attrs[1].name.to_string(), "allow");
+        assert_eq!(
+            attrs.to_token_stream().to_string(),
+            "#! [doc = \"Foo\"] #! [allow (unused)]"
+        );
+
+        let attrs = input.parse_attributes().unwrap();
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(attrs[0].style, AttrStyle::Outer);
+        assert_eq!(attrs.to_token_stream().to_string(), "# [doc = \"Bar\"]");
+        assert!(input.next().is_none());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_cfg_predicate() {
+        let mut input = TokenStream::from_str(
+            r#"all(unix, any(feature = "std", test), not(debug_assertions))"#,
+        )
+        .unwrap()
+        .into_token_iter();
+        let predicate = input.parse_cfg_predicate().unwrap();
+        assert!(input.next().is_none());
+
+        match &predicate {
+            CfgPredicate::All(predicates) => {
+                assert_eq!(predicates.len(), 3);
+                assert!(matches!(&predicates[1], CfgPredicate::Any(any) if any.len() == 2));
+                assert!(matches!(&predicates[2], CfgPredicate::Not(_)));
+            }
+            _ => panic!(),
+        }
+        assert_eq!(
+            predicate.to_token_stream().to_string(),
+            r#"all (unix , any (feature = "std" , test) , not (debug_assertions))"#
+        );
+
+        let mut input = TokenStream::from_str("not(unix, windows)")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_cfg_predicate().is_err());
+
+        let mut input = TokenStream::from_str("one_of(unix)")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_cfg_predicate().is_err());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_visibility() {
+        let mut input = TokenStream::from_str("pub(crate) pub foo")
+            .unwrap()
+            .into_token_iter();
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Restricted(..)
+        ));
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Public(_)
+        ));
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Inherited
+        ));
+        assert!(input.next().is_some());
+
+        let mut input = TokenStream::from_str("pub (u8, u8) pub(in crate::foo) pub(self)")
+            .unwrap()
+            .into_token_iter();
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Public(_)
+        ));
+        assert_eq!(input.try_group().unwrap().to_string(), "(u8 , u8)");
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Restricted(..)
+        ));
+        assert!(matches!(
+            input.parse_visibility_full().unwrap(),
+            Visibility::Restricted(..)
+        ));
+        assert!(input.next().is_none());
+
+        let mut input = TokenStream::from_str("pub(super) struct")
+            .unwrap()
+            .into_token_iter();
+        input.parse_visibility().unwrap();
+        input.expect_ident("struct").unwrap();
+    }
+
+    #[test]
+    fn test_tokeniter_parse_type() {
+        let mut input = TokenStream::from_str("HashMap<u8, Vec<T>>, Box<dyn Fn(u8) -> u8> = x")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(
+            input.parse_type().unwrap().to_string(),
+            "HashMap < u8 , Vec < T >>"
+        );
+        input.expect_punct(',').unwrap();
+        assert_eq!(
+            input.parse_type().unwrap().to_string(),
+            "Box < dyn Fn (u8) -> u8 >"
+        );
+        input.expect_punct('=').unwrap();
+
+        let mut input = TokenStream::from_str(", u8").unwrap().into_token_iter();
+        assert!(input.parse_type().is_err());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_generics() {
+        let mut input = TokenStream::from_str(
+            "<'a, 'b: 'a, T: Iterator<Item = &'a u8> + 'b, F: Fn() -> T, const N: usize = 4> where T: Clone, {}",
+        )
+        .unwrap()
+        .into_token_iter();
+
+        let mut generics = input.parse_generics().unwrap();
+        generics.where_clause = input.parse_where_clause().unwrap();
+        assert!(input.expect_group(Delimiter::Brace).is_ok());
+        assert!(input.next().is_none());
+
+        assert_eq!(generics.params.len(), 5);
+        assert_eq!(generics.where_clause.len(), 1);
+        assert_eq!(
+            generics.impl_generics().to_string(),
+            "< 'a , 'b : 'a , T : Iterator < Item = &'a u8 > + 'b , F : Fn () -> T , const N : usize , >"
+        );
+        assert_eq!(
+            generics.type_generics().to_string(),
+            "< 'a , 'b , T , F , N , >"
+        );
+        assert_eq!(generics.where_clause().to_string(), "where T : Clone ,");
+        assert!(generics
+            .to_token_stream()
+            .to_string()
+            .ends_with("const N : usize = 4 , >"));
+
+        let mut input = TokenStream::from_str("{}").unwrap().into_token_iter();
+        assert!(input.parse_generics().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_union() {
+        let mut input = TokenStream::from_str(
+            "#[repr(C)] pub union Foo<T: Copy> where T: Default { pub a: u32, #[doc = \"b\"] b: T }",
+        )
+        .unwrap()
+        .into_token_iter();
+
+        let union = input.parse_union().unwrap();
+        assert!(input.next().is_none());
+        assert_eq!(union.attrs.len(), 1);
+        assert_eq!(union.name.to_string(), "Foo");
+        assert_eq!(union.generics.params.len(), 1);
+        assert_eq!(union.generics.where_clause.len(), 1);
+        assert_eq!(union.fields.len(), 2);
+        assert_eq!(union.fields[1].name.to_string(), "b");
+        assert_eq!(union.fields[1].ty.to_string(), "T");
+        assert_eq!(
+            union.to_token_stream().to_string(),
+            "# [repr (C)] pub union Foo < T : Copy , > where T : Default , \
+             { pub a : u32 , # [doc = \"b\"] b : T , }"
+        );
+
+        let mut input = TokenStream::from_str("struct Foo { a: u32 }")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_union().is_err());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_fn() {
+        let mut input = TokenStream::from_str(concat!(
+            "#[inline] pub(crate) const async unsafe extern \"C\" fn foo<'a, T>",
+            "(&'a mut self, (a, b): (u8, u8), mut c: foo::Bar<T>) -> Option<T> where T: Copy",
+            "{ let x = 1; }",
+        ))
+        .unwrap()
+        .into_token_iter();
+
+        let item = input.parse_fn().unwrap();
+        assert!(input.next().is_none());
+        assert_eq!(item.attrs.len(), 1);
+        assert!(matches!(item.vis, Visibility::Restricted(..)));
+
+        let sig = &item.sig;
+        assert!(sig.constness.is_some());
+        assert!(sig.asyncness.is_some());
+        assert!(sig.unsafety.is_some());
+        assert_eq!(
+            sig.abi.as_ref().unwrap().1.as_ref().unwrap().to_string(),
+            "\"C\""
+        );
+        assert_eq!(sig.name.to_string(), "foo");
+        assert_eq!(sig.generics.params.len(), 2);
+        assert_eq!(sig.generics.where_clause.len(), 1);
+        assert_eq!(sig.output.as_ref().unwrap().1.to_string(), "Option < T >");
+        assert_eq!(sig.inputs.len(), 3);
+
+        match &sig.inputs.items[0] {
+            FnArg::Receiver(receiver) => {
+                let lifetime = receiver.reference.as_ref().unwrap().1.as_ref();
+                assert_eq!(lifetime.unwrap().to_string(), "'a");
+                assert!(receiver.mutability.is_some());
+            }
+            FnArg::Typed(_) => panic!(),
+        }
+        match &sig.inputs.items[2] {
+            FnArg::Typed(pat_type) => {
+                assert_eq!(pat_type.pat.to_token_stream().to_string(), "mut c");
+                assert_eq!(pat_type.ty.to_string(), "foo :: Bar < T >");
+            }
+            FnArg::Receiver(_) => panic!(),
+        }
+        assert_eq!(item.body.stream().to_string(), "let x = 1 ;");
+
+        let mut input = TokenStream::from_str("fn foo(self: Box<Self>, Foo { x, .. }: Foo);")
+            .unwrap()
+            .into_token_iter();
+        let sig = input.parse_signature().unwrap();
+        assert!(matches!(&sig.inputs.items[0], FnArg::Receiver(r) if r.ty.is_some()));
+        assert!(matches!(&sig.inputs.items[1], FnArg::Typed(_)));
+        assert!(sig.output.is_none());
+        input.expect_punct(';').unwrap();
+
+        let mut input = TokenStream::from_str("fn f(a: u8) -> u8; fn g(a: u8, b: u8,);")
+            .unwrap()
+            .into_token_iter();
+        let sig = input.parse_signature().unwrap();
+        assert_eq!(sig.to_token_stream().to_string(), "fn f (a : u8) -> u8");
+        input.expect_punct(';').unwrap();
+        let sig = input.parse_signature().unwrap();
+        assert!(sig.inputs.has_trailing());
+        assert_eq!(
+            sig.to_token_stream().to_string(),
+            "fn g (a : u8 , b : u8 ,)"
+        );
+
+        let mut input = TokenStream::from_str("fn foo();")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_fn().is_err());
+    }
+
+    #[test]
+    fn test_tokeniter_parse_impl() {
+        let mut input = TokenStream::from_str(concat!(
+            "#[rpc] unsafe impl<T: Clone> foo::Service<T> for Server<T> where T: Send {",
+            "    const MAX: usize = { 1; 2 };",
+            "    #[doc = \"Items\"] type Item<'a> = &'a T where T: 'a;",
+            "    pub async fn call(&self, request: T) -> T { request }",
+            "    const fn new() -> Self { Self }",
+            "    helpers::impl_debug!(T);",
+            "}",
+        ))
+        .unwrap()
+        .into_token_iter();
+
+        let item = input.parse_impl().unwrap();
+        assert!(input.next().is_none());
+        assert_eq!(item.attrs.len(), 1);
+        assert!(item.unsafety.is_some());
+        assert_eq!(item.generics.params.len(), 1);
+        assert_eq!(item.generics.where_clause.len(), 1);
+        let (negative, path, _) = item.trait_.as_ref().unwrap();
+        assert!(negative.is_none());
+        assert_eq!(path.to_string(), "foo :: Service < T >");
+        assert_eq!(item.self_ty.to_string(), "Server < T >");
+        assert_eq!(item.items.len(), 5);
+
+        assert!(matches!(&item.items[0], ImplItem::Const(c) if c.name.to_string() == "MAX"));
+        match &item.items[1] {
+            ImplItem::Type(ty) => {
+                assert_eq!(ty.attrs.len(), 1);
+                assert_eq!(ty.name.to_string(), "Item");
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&item.items[2], ImplItem::Fn(f) if f.sig.asyncness.is_some()));
+        assert!(matches!(&item.items[3], ImplItem::Fn(f) if f.sig.constness.is_some()));
+        assert!(matches!(&item.items[4], ImplItem::Macro(m) if m.name.to_string() == "impl_debug"));
+
+        let mut input = TokenStream::from_str("impl !Send for Foo {} impl Foo { fn foo() {} }")
+            .unwrap()
+            .into_token_iter();
+        let item = input.parse_impl().unwrap();
+        assert!(item.trait_.unwrap().0.is_some());
+        let item = input.parse_impl().unwrap();
+        assert!(item.trait_.is_none());
+        assert_eq!(item.self_ty.to_string(), "Foo");
+        assert!(input.next().is_none());
+
+        let source = concat!(
+            "#[rpc] impl<T> Service for Server<T> {",
+            "    #![allow(unused)]",
+            "    default fn call(&self) {}",
+            "    default type Item = T;",
+            "    default const MAX: usize = 1;",
+            "    default!();",
+            "}",
+        );
+        let mut input = TokenStream::from_str(source).unwrap().into_token_iter();
+        let item = input.parse_impl().unwrap();
+        assert!(input.next().is_none());
+        assert_eq!(item.attrs.len(), 2);
+        assert_eq!(item.attrs[0].style, AttrStyle::Outer);
+        assert_eq!(item.attrs[1].style, AttrStyle::Inner);
+        assert_eq!(item.items.len(), 4);
+        match &item.items[0] {
+            ImplItem::Fn(f) => {
+                assert!(f.defaultness.is_some());
+                assert_eq!(f.sig.name.to_string(), "call");
+            }
+            _ => panic!(),
+        }
+        match &item.items[1] {
+            ImplItem::Type(ty) => {
+                assert_eq!(ty.name.to_string(), "Item");
+                assert_eq!(ty.tokens.to_string(), "default type Item = T ;");
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&item.items[2], ImplItem::Const(c) if c.name.to_string() == "MAX"));
+        assert!(matches!(&item.items[3], ImplItem::Macro(m) if m.name.to_string() == "default"));
+
+        let mut output = item.to_token_stream().into_token_iter();
+        let item = output.parse_impl().unwrap();
+        assert!(output.next().is_none());
+        assert_eq!(item.attrs.len(), 2);
+        assert_eq!(item.attrs[1].style, AttrStyle::Inner);
+        assert!(matches!(&item.items[0], ImplItem::Fn(f) if f.defaultness.is_some()));
+        assert_eq!(item.items.len(), 4);
+    }
+
+    #[test]
+    fn test_tokeniter_parse_trait() {
+        let mut input = TokenStream::from_str(concat!(
+            "pub unsafe trait Store<K>: Clone + Send where K: Hash {",
+            "    /// Get a value.\n",
+            "    fn get(&self, key: &K) -> Option<Self::Value>;",
+            "    fn len(&self) -> usize { 0 }",
+            "    type Value: Clone + 'static;",
+            "    type Iter<'a>: Iterator<Item = &'a Self::Value> where Self: 'a;",
+            "    type Error = ();",
+            "    const CAPACITY: usize;",
+            "    const NAME: &'static str = concat!(\"a\", \"b\");",
+            "    declare!();",
+            "}",
+        ))
+        .unwrap()
+        .into_token_iter();
+
+        let item = input.parse_trait().unwrap();
+        assert!(input.next().is_none());
+        assert!(matches!(item.vis, Visibility::Public(_)));
+        assert!(item.unsafety.is_some());
+        assert_eq!(item.name.to_string(), "Store");
+        assert_eq!(item.generics.params.len(), 1);
+        assert_eq!(item.generics.where_clause.len(), 1);
+        assert_eq!(item.supertraits.to_string(), "Clone + Send");
+        assert_eq!(item.items.len(), 8);
+
+        match &item.items[0] {
+            TraitItem::Fn(f) => {
+                assert_eq!(f.attrs.len(), 1);
+                assert_eq!(f.sig.name.to_string(), "get");
+                assert!(f.default.is_none());
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&item.items[1], TraitItem::Fn(f) if f.default.is_some()));
+        match &item.items[2] {
+            TraitItem::Type(ty) => {
+                assert_eq!(ty.name.to_string(), "Value");
+                assert_eq!(ty.bounds.to_string(), "Clone + 'static");
+                assert!(ty.default.is_none());
+            }
+            _ => panic!(),
+        }
+        match &item.items[3] {
+            TraitItem::Type(ty) => {
+                assert_eq!(ty.generics.params.len(), 1);
+                assert_eq!(ty.generics.where_clause.len(), 1);
+            }
+            _ => panic!(),
+        }
+        assert!(
+            matches!(&item.items[4], TraitItem::Type(ty) if ty.default.as_ref().unwrap().to_string() == "()")
+        );
+        assert!(matches!(&item.items[5], TraitItem::Const(c) if c.default.is_none()));
+        match &item.items[6] {
+            TraitItem::Const(c) => {
+                assert_eq!(c.ty.to_string(), "&'static str");
+                assert_eq!(
+                    c.default.as_ref().unwrap().to_string(),
+                    "concat ! (\"a\" , \"b\")"
+                );
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&item.items[7], TraitItem::Macro(_)));
 
-            let mut group = self.expect_group(Delimiter::Bracket)?;
-            let ident = group.try_ident()?;
+        let mut output = item.to_token_stream().into_token_iter();
+        let item = output.parse_trait().unwrap();
+        assert_eq!(item.items.len(), 8);
+        assert!(output.next().is_none());
 
-            attrs.push(Attribute {
-                name: ident,
-                tree: group.collect::<TokenStream>().into_token_iter(),
-            });
+        let mut input = TokenStream::from_str("trait T { const X: bool = a || b; fn f(); }")
+            .unwrap()
+            .into_token_iter();
+        let item = input.parse_trait().unwrap();
+        assert_eq!(item.items.len(), 2);
+        match &item.items[0] {
+            TraitItem::Const(c) => assert_eq!(c.default.as_ref().unwrap().to_string(), "a || b"),
+            _ => panic!(),
         }
 
-        Ok(attrs)
+        let source = concat!(
+            "pub unsafe trait Store: Clone { type Value: Clone = (); const N: usize = 1; ",
+            "fn get(&self, key: &u8, _: u8) -> u8; fn len(self: Box<Self>) { 0 } }",
+        );
+        let mut input = TokenStream::from_str(source).unwrap().into_token_iter();
+        let item = input.parse_trait().unwrap();
+        assert_eq!(
+            item.to_token_stream().to_string(),
+            TokenStream::from_str(source).unwrap().to_string(),
+        );
+
+        let source = "trait Foo { #![allow(unused)] fn foo(); }";
+        let mut input = TokenStream::from_str(source).unwrap().into_token_iter();
+        let item = input.parse_trait().unwrap();
+        assert_eq!(item.attrs.len(), 1);
+        assert_eq!(item.attrs[0].style, AttrStyle::Inner);
+        assert_eq!(item.items.len(), 1);
+        assert_eq!(
+            item.to_token_stream().to_string(),
+            TokenStream::from_str(source).unwrap().to_string(),
+        );
     }
 
-    fn parse_visibility(&mut self) -> Result<(), TokenStream> {
-        match self.peek() {
-            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => self.next(),
-            _ => return Ok(()),
-        };
+    #[test]
+    fn test_tokeniter_parse_item() {
+        let input = TokenStream::from_str(concat!(
+            "#[derive(Debug)] pub struct Foo<T> where T: Clone { a: T }",
+            "struct Unit;",
+            "pub(crate) enum Bar { A, B }",
+            "union Baz { a: u8 }",
+            "pub const unsafe fn qux() {}",
+            "unsafe impl Send for Foo {}",
+            "unsafe trait Marker {}",
+            "mod inner;",
+            "pub mod outer { fn f() {} }",
+            "const X: u8 = { 1 };",
+            "static mut Y: u8 = 0;",
+            "pub type Z = Vec<u8>;",
+            "use std::{fmt, io};",
+            "extern crate alloc as core_alloc;",
+            "extern \"C\" { fn abs(x: i32) -> i32; }",
+            "unsafe extern { static X: u8; }",
+            "macro_rules! m { () => {} }",
+            "m!();",
+        ))
+        .unwrap();
 
-        match self.peek() {
-            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
-                self.next();
+        let mut input = input.into_token_iter();
+        let mut kinds = vec![];
+        let mut names = vec![];
+        while input.peek().is_some() {
+            let item = input.parse_item().unwrap();
+            kinds.push(item.kind());
+            match &item {
+                Item::Struct(item)
+                | Item::Const(item)
+                | Item::Static(item)
+                | Item::ExternCrate(item) => {
+                    names.push(item.name.to_string());
+                }
+                Item::Use(item) => names.push(item.tree.to_string()),
+                _ => (),
+            }
+        }
+
+        assert_eq!(
+            kinds,
+            [
+                "struct", "struct", "enum", "union", "fn", "impl", "trait", "mod", "mod", "const",
+                "static", "type", "use", "extern", "extern", "extern", "macro", "macro",
+            ]
+        );
+        assert_eq!(
+            names,
+            ["Foo", "Unit", "X", "Y", "std :: { fmt , io }", "alloc"]
+        );
+
+        let source = "#[cfg(unix)] unsafe extern \"C\" { fn abs(x: i32) -> i32; } pub use a::b;";
+        let mut input = TokenStream::from_str(source).unwrap().into_token_iter();
+        let item = input.parse_item().unwrap();
+        match &item {
+            Item::ForeignMod(item) => {
+                assert_eq!(item.attrs.len(), 1);
+                assert!(item.unsafety.is_some());
+                assert_eq!(item.abi.1.as_ref().unwrap().to_string(), "\"C\"");
             }
-            _ => return Ok(()),
+            _ => panic!(),
         }
+        let mut output = item.to_token_stream();
+        output.extend(input.parse_item().unwrap().to_token_stream());
+        assert!(input.next().is_none());
+        assert_eq!(
+            output.to_string(),
+            TokenStream::from_str(source).unwrap().to_string()
+        );
+
+        let mut input = TokenStream::from_str("pub(crate) struct Foo { a: u8 } impl Foo {}")
+            .unwrap()
+            .into_token_iter();
+        let item = input.parse_item().unwrap();
+        assert_eq!(
+            item.to_token_stream().to_string(),
+            "pub (crate) struct Foo { a : u8 }"
+        );
+        assert_eq!(input.parse_item().unwrap().kind(), "impl");
+        assert!(input.next().is_none());
 
-        Ok(())
+        let mut input = TokenStream::from_str("#[attr] pub 42")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_item().is_err());
     }
 
-    fn parse_path(&mut self) -> Result<(String, Span), TokenStream> {
-        let mut path = String::new();
-        let mut span = None;
-        let mut nesting = 0;
+    #[test]
+    fn test_tokeniter_parse_punctuated() {
+        let mut input = TokenStream::from_str("a, b, c").unwrap().into_token_iter();
+        let list = input
+            .parse_punctuated(',', TokenIterExt::try_ident)
+            .unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.separators.len(), 2);
+        assert!(!list.has_trailing());
 
-        while let Some(tree) = self.peek() {
-            match tree {
-                TokenTree::Punct(punct)
-                    if [',', ';'].contains(&punct.as_char()) && nesting == 0 =>
-                {
-                    break
-                }
-                TokenTree::Punct(punct) => {
-                    let ch = punct.as_char();
+        let mut input = TokenStream::from_str("1 + 2 +").unwrap().into_token_iter();
+        let list = input.parse_punctuated('+', TokenIterExt::try_lit).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.has_trailing());
 
-                    // Handle nesting with `<...>`
-                    if ch == '<' {
-                        nesting += 1;
-                    } else if ch == '>' && punct.spacing() == Spacing::Joint {
-                        nesting -= 1;
-                    }
+        let mut input = TokenStream::new().into_token_iter();
+        let list = input
+            .parse_punctuated(',', TokenIterExt::try_ident)
+            .unwrap();
+        assert!(list.is_empty());
+        assert!(!list.has_trailing());
 
-                    span.get_or_insert_with(|| punct.span());
-                    path.push(ch);
-                }
-                TokenTree::Ident(ident) => {
-                    span.get_or_insert_with(|| ident.span());
-                    path.push_str(&ident.to_string());
-                }
-                TokenTree::Group(group) => {
-                    span.get_or_insert(group.span());
-                    let mut stream = group.stream().into_token_iter();
+        let mut input = TokenStream::from_str("a, b c").unwrap().into_token_iter();
+        let error = input
+            .parse_punctuated(',', TokenIterExt::try_ident)
+            .unwrap_err();
+        assert!(error.to_string().contains("Expected `,`"));
 
-                    match group.delimiter() {
-                        Delimiter::Parenthesis => {
-                            // Tuples are comma-separated paths.
-                            path.push('(');
-                            while stream.peek().is_some() {
-                                let (inner, _span) = stream.parse_path()?;
-                                path.push_str(&inner);
-                                if stream.peek().is_some() {
-                                    stream.expect_punct(',')?;
-                                    path.push_str(", ");
-                                }
-                            }
-                            if path.ends_with(' ') {
-                                path.pop();
-                            }
-                            path.push(')');
-                        }
-                        Delimiter::Bracket => {
-                            // Arrays are in `[path; size]` form.
-                            path.push('[');
-                            let (inner, _span) = stream.parse_path()?;
-                            path.push_str(&inner);
-                            stream.expect_punct(';')?;
-                            path.push_str("; ");
-                            path.push_str(&stream.try_lit()?.to_string());
-                            path.push(']');
-                        }
-                        _ => return Err(spanned_error("Unexpected token", group.span())),
-                    }
-                }
-                TokenTree::Literal(_) => {
-                    return Err(spanned_error("Unexpected token", self.next().as_span()))
-                }
-            }
+        let mut input = TokenStream::from_str("((u8,), (u8, u16))")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(input.parse_path().unwrap().0, "((u8,), (u8, u16))");
+    }
 
-            self.next();
-        }
+    #[test]
+    fn test_tokeniter_fork() {
+        let mut input = TokenStream::from_str("a b c d").unwrap().into_token_iter();
+        assert_eq!(input.peek_n(2).unwrap().to_string(), "c");
+        assert!(input.peek_n(4).is_none());
 
-        let span =
-            span.ok_or_else(|| spanned_error("Unexpected end of stream", Span::call_site()))?;
+        let mut fork = input.fork();
+        fork.next();
+        fork.next();
+        assert_eq!(input.offset(), 0);
+        assert_eq!(fork.offset(), 2);
+        assert_eq!(input.peek().unwrap().to_string(), "a");
 
-        Ok((path, span))
+        input.advance_to(&fork);
+        assert_eq!(input.offset(), 2);
+        assert_eq!(input.len(), 2);
+        assert_eq!(input.next().unwrap().to_string(), "c");
+        assert!(input.next_if(|tree| tree.to_string() == "x").is_none());
+        assert!(input.next_if(|tree| tree.to_string() == "d").is_some());
+        assert!(input.next().is_none());
     }
 
-    fn expect_group(&mut self, expect: Delimiter) -> Result<TokenIter, TokenStream> {
-        self.try_group()
-            .and_then(|group| {
-                let delim = group.delimiter();
-                if delim == expect {
-                    Ok(group.stream().into_token_iter())
-                } else {
-                    let expect = match expect {
-                        Delimiter::Brace => "{",
-                        Delimiter::Bracket => "[",
-                        Delimiter::None => "delimiter",
-                        Delimiter::Parenthesis => "(",
-                    };
+    #[test]
+    #[should_panic(expected = "`advance_to` requires a fork of this cursor")]
+    fn test_tokeniter_advance_to_foreign_fork() {
+        let mut input = TokenStream::from_str("(a b) c").unwrap().into_token_iter();
+        let mut fork = input.fork().expect_group(Delimiter::Parenthesis).unwrap();
+        fork.next();
+        input.advance_to(&fork);
+    }
 
-                    Err(spanned_error(format!("Expected `{expect}`"), group.span()))
-                }
-            })
-            .map_err(|err| {
-                self.next();
-                err
-            })
+    #[test]
+    fn test_tokeniter_op() {
+        let mut input = TokenStream::from_str("-> => ..= <<= : :: - >")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.peek_op("->"));
+        assert!(input.peek_op("-"));
+        assert!(!input.peek_op("=>"));
+        assert!(!input.peek_op(""));
+        input.expect_op("->").unwrap();
+        input.expect_op("=>").unwrap();
+        assert!(!input.peek_op("..=="));
+        input.expect_op("..=").unwrap();
+        input.expect_op("<<=").unwrap();
+        assert!(!input.peek_op("::"));
+        input.expect_op(":").unwrap();
+        input.expect_op("::").unwrap();
+        assert!(!input.peek_op("->"));
+        let error = input.expect_op("->").unwrap_err();
+        assert!(error.to_string().contains("Expected `->`"));
+        input.expect_op(">").unwrap();
+        assert!(input.next().is_none());
+
+        let tokens = PunctSeq::new("..=").to_token_stream();
+        assert_eq!(tokens.to_string(), "..=");
+        let input = tokens.into_token_iter();
+        assert!(input.peek_op("..="));
+        assert!(input.peek_op(".."));
     }
 
-    fn expect_ident(&mut self, expect: &str) -> Result<(), TokenStream> {
-        self.try_ident()
-            .and_then(|ident| {
-                if ident.to_string() == expect {
-                    Ok(())
-                } else {
-                    Err(spanned_error(format!("Expected `{expect}`"), ident.span()))
-                }
-            })
-            .map_err(|err| {
-                self.next();
-                err
-            })
+    #[test]
+    fn test_tokeniter_lifetime() {
+        let mut input = TokenStream::from_str("'a 'static foo")
+            .unwrap()
+            .into_token_iter();
+        let lifetime = input.try_lifetime().unwrap();
+        assert_eq!(lifetime.to_string(), "'a");
+        assert_eq!(lifetime, Lifetime::new("'a", Span::call_site()));
+        assert_eq!(lifetime.to_token_stream().to_string(), "'a");
+        assert!(input.expect_lifetime("b").is_err());
+        assert_eq!(input.try_ident().unwrap().to_string(), "foo");
+        assert!(input.try_lifetime().is_err());
+        assert!(input.next().is_none());
+
+        let mut input = TokenStream::from_str("'static").unwrap().into_token_iter();
+        input.expect_lifetime("'static").unwrap();
+        assert!(input.next().is_none());
+
+        let mut input = TokenStream::from_str("<'a, 'b: 'a, T: Trait<'a> + 'b>")
+            .unwrap()
+            .into_token_iter();
+        let generics = input.parse_generics().unwrap();
+        let lifetimes = generics
+            .lifetimes()
+            .map(|lt| lt.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lifetimes, ["'a", "'b"]);
+        let bounds = generics.params[2].bound_lifetimes();
+        let bounds = bounds.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(bounds, ["'a", "'b"]);
+        assert!(generics.params[2].lifetime().is_none());
+
+        let mut input = TokenStream::from_str("&'a str, &'a mut dyn Trait")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(input.parse_path().unwrap().0, "&'a str");
+        input.expect_punct(',').unwrap();
+        assert_eq!(input.parse_path().unwrap().0, "&'a mut dyn Trait");
     }
 
-    fn expect_punct(&mut self, expect: char) -> Result<(), TokenStream> {
-        self.try_punct()
-            .and_then(|punct| {
-                if punct.as_char() == expect {
-                    Ok(())
-                } else {
-                    Err(spanned_error(format!("Expected `{expect}`"), punct.span()))
-                }
-            })
-            .map_err(|err| {
-                self.next();
-                err
-            })
+    #[test]
+    fn test_tokeniter_keywords() {
+        let mut input = TokenStream::from_str("move r#async get post 42")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(input.peek_keyword(&["async", "move"]), Some("move"));
+        assert_eq!(input.try_ident().unwrap().to_string(), "move");
+        assert_eq!(input.peek_keyword(&["async", "move"]), None);
+        assert!(input.expect_one_of(&["async"]).is_err());
+
+        let ident = input.expect_one_of(&["get", "post"]).unwrap();
+        assert_eq!(ident.to_string(), "get");
+        let error = input.expect_one_of(&["get", "put", "delete"]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Expected one of `get`, `put`, `delete`"));
+        assert_eq!(input.peek_keyword(&["get"]), None);
+        let error = input.expect_one_of(&["get"]).unwrap_err();
+        assert!(error.to_string().contains("Expected `get`"));
+        assert!(input.next().is_none());
     }
 
-    fn try_group(&mut self) -> Result<Group, TokenStream> {
-        match self.next_if(|token| matches!(token, TokenTree::Group(_))) {
-            Some(TokenTree::Group(group)) => Ok(group),
-            tree => Err(spanned_error("Expected group", tree.as_span())),
-        }
+    #[test]
+    fn test_tokeniter_parse() {
+        let mut input = TokenStream::from_str("#[a] #[b(c)] foo 42 Vec<u8>, 'a")
+            .unwrap()
+            .into_token_iter();
+        let attrs = input.parse::<Option<Attribute>>().unwrap();
+        assert_eq!(attrs.unwrap().name.to_string(), "a");
+        let attr = input.parse::<Attribute>().unwrap();
+        assert_eq!(attr.name.to_string(), "b");
+        assert!(input.parse::<Option<Attribute>>().unwrap().is_none());
+        assert!(input.parse::<Option<Literal>>().unwrap().is_none());
+        assert_eq!(input.parse::<Ident>().unwrap().to_string(), "foo");
+        assert_eq!(input.parse::<Literal>().unwrap().to_string(), "42");
+        let ty = input.parse::<Type>().unwrap();
+        assert_eq!(ty.to_token_stream().to_string(), "Vec < u8 >");
+        assert_eq!(input.parse::<Punct>().unwrap().as_char(), ',');
+        assert_eq!(input.parse::<Lifetime>().unwrap().to_string(), "'a");
+        assert!(input.parse::<TokenTree>().is_err());
+
+        let mut input = TokenStream::from_str("a b c").unwrap().into_token_iter();
+        let idents = input.parse::<Vec<Ident>>().unwrap();
+        assert_eq!(idents.len(), 3);
+
+        let mut input = TokenStream::from_str("a b 1").unwrap().into_token_iter();
+        assert!(input.parse::<Vec<Ident>>().is_err());
+
+        let mut input = TokenStream::from_str("foo::Bar<T>")
+            .unwrap()
+            .into_token_iter();
+        let path = input.parse::<Path>().unwrap();
+        assert_eq!(path.path, "foo::Bar<T>");
+        assert_eq!(path.to_token_stream().to_string(), "foo :: Bar < T >");
+
+        let mut input = TokenStream::from_str("# foo").unwrap().into_token_iter();
+        assert!(input.parse::<Option<Attribute>>().is_err());
     }
 
-    fn try_ident(&mut self) -> Result<Ident, TokenStream> {
-        match self.next_if(|token| matches!(token, TokenTree::Ident(_))) {
-            Some(TokenTree::Ident(ident)) => Ok(ident),
-            tree => Err(spanned_error("Expected identifier", tree.as_span())),
-        }
+    #[test]
+    fn test_tokeniter_expect_end() {
+        let mut input = TokenStream::from_str("long extra")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(input.try_ident().unwrap().to_string(), "long");
+        let error = input.expect_end().unwrap_err();
+        assert!(error.to_string().contains("Unexpected token"));
+        assert_eq!(input.try_ident().unwrap().to_string(), "extra");
+        assert!(input.expect_end().is_ok());
+
+        let mut input = TokenStream::from_str("foo::bar").unwrap().into_token_iter();
+        let (path, _span) = input.parse_all(TokenIterExt::parse_path).unwrap();
+        assert_eq!(path, "foo::bar");
+
+        let mut input = TokenStream::from_str("long extra")
+            .unwrap()
+            .into_token_iter();
+        assert!(input.parse_all(TokenIterExt::try_ident).is_err());
     }
 
-    fn try_lit(&mut self) -> Result<Literal, TokenStream> {
-        match self.next_if(|token| matches!(token, TokenTree::Literal(_))) {
-            Some(TokenTree::Literal(lit)) => Ok(lit),
-            tree => Err(spanned_error("Expected literal", tree.as_span())),
+    #[test]
+    fn test_tokeniter_parse_expr() {
+        let mut input = TokenStream::from_str(concat!(
+            "42, true, -x, a + b * 2, foo::BAR, Vec::<u8, u16>::new(), f(1, 2), x.foo::<T>(1), ",
+            "{ a }, vec![1, 2], |a, b| a + b, || 1, a as u8, x.y, <T as Trait>::f(a, b), ",
+            "a < b, a..=b, !x.is_empty(), move |x| x, if a { b } else { c }, a || b, a | b, ",
+            "(1, 2), (a + b), &|a, b| a + b, f(x) + |a, b| a, a = |a, b| a, x? => y; z",
+        ))
+        .unwrap()
+        .into_token_iter();
+
+        let expected = [
+            ("42", ExprKind::Lit),
+            ("true", ExprKind::Lit),
+            ("- x", ExprKind::Unary),
+            ("a + b * 2", ExprKind::Binary),
+            ("foo :: BAR", ExprKind::Path),
+            ("Vec ::< u8 , u16 >:: new ()", ExprKind::Call),
+            ("f (1 , 2)", ExprKind::Call),
+            ("x . foo ::< T > (1)", ExprKind::MethodCall),
+            ("{ a }", ExprKind::Block),
+            ("vec ! [1 , 2]", ExprKind::Macro),
+            ("| a , b | a + b", ExprKind::Other),
+            ("|| 1", ExprKind::Other),
+            ("a as u8", ExprKind::Other),
+            ("x . y", ExprKind::Other),
+            ("< T as Trait >:: f (a , b)", ExprKind::Call),
+            ("a < b", ExprKind::Binary),
+            ("a ..= b", ExprKind::Binary),
+            ("! x . is_empty ()", ExprKind::Unary),
+            ("move | x | x", ExprKind::Other),
+            ("if a { b } else { c }", ExprKind::Other),
+            ("a || b", ExprKind::Binary),
+            ("a | b", ExprKind::Binary),
+            ("(1 , 2)", ExprKind::Other),
+            ("(a + b)", ExprKind::Other),
+            ("&| a , b | a + b", ExprKind::Unary),
+            ("f (x) + | a , b | a", ExprKind::Binary),
+            ("a = | a , b | a", ExprKind::Binary),
+        ];
+        for (tokens, kind) in expected {
+            let expr = input.parse_expr().unwrap();
+            assert_eq!(expr.tokens.to_string(), tokens);
+            assert_eq!(expr.kind, kind, "{tokens}");
+            input.expect_punct(',').unwrap();
         }
+
+        assert_eq!(input.parse_expr_tokens().unwrap().to_string(), "x ?");
+        input.expect_op("=>").unwrap();
+        assert_eq!(input.parse_expr_tokens().unwrap().to_string(), "y");
+        input.expect_punct(';').unwrap();
+        assert_eq!(input.parse_expr().unwrap().kind, ExprKind::Path);
+        assert!(input.parse_expr_tokens().is_err());
     }
 
-    fn try_punct(&mut self) -> Result<Punct, TokenStream> {
-        match self.next_if(|token| matches!(token, TokenTree::Punct(_))) {
-            Some(TokenTree::Punct(punct)) => Ok(punct),
-            tree => Err(spanned_error("Expected punctuation", tree.as_span())),
+    #[test]
+    fn test_tokeniter_parse_pat() {
+        let mut input = TokenStream::from_str(concat!(
+            "x, ref mut y, _, .., 42, -1, &mut (a, b), (a,), (a), [first, .., last], ",
+            "Some(x), Foo::Bar, Foo { x, ref y, z: (c, d), .. }, n @ 1, true",
+        ))
+        .unwrap()
+        .into_token_iter();
+        let pats = input
+            .parse_punctuated(',', TokenIterExt::parse_pat)
+            .unwrap();
+        assert_eq!(pats.len(), 15);
+        let pats = pats.items;
+
+        assert!(matches!(&pats[0], Pat::Ident(pat) if pat.by_ref.is_none()));
+        match &pats[1] {
+            Pat::Ident(pat) => {
+                assert!(pat.by_ref.is_some());
+                assert!(pat.mutability.is_some());
+                assert_eq!(pat.ident.to_string(), "y");
+            }
+            _ => panic!(),
         }
-    }
-}
+        assert!(matches!(&pats[2], Pat::Wild(_)));
+        assert!(matches!(&pats[3], Pat::Rest(_)));
+        assert!(matches!(&pats[4], Pat::Lit(None, _)));
+        assert!(matches!(&pats[5], Pat::Lit(Some(_), _)));
+        match &pats[6] {
+            Pat::Reference(pat) => {
+                assert!(pat.mutability.is_some());
+                assert!(matches!(&*pat.pat, Pat::Tuple(_, elems) if elems.len() == 2));
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&pats[7], Pat::Tuple(_, elems) if elems.has_trailing()));
+        assert!(matches!(&pats[8], Pat::Tuple(_, elems) if !elems.has_trailing()));
+        assert!(matches!(&pats[9], Pat::Slice(_, elems) if elems.len() == 3));
+        assert!(matches!(&pats[10], Pat::TupleStruct(pat) if pat.elems.len() == 1));
+        assert!(matches!(&pats[11], Pat::Path(_)));
+        match &pats[12] {
+            Pat::Struct(pat) => {
+                assert_eq!(pat.path.to_string(), "Foo");
+                assert_eq!(pat.fields.len(), 3);
+                assert!(pat.fields.items[0].colon.is_none());
+                assert!(pat.fields.items[1].colon.is_none());
+                assert!(pat.fields.items[2].colon.is_some());
+                assert!(pat.rest.is_some());
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(&pats[13], Pat::Ident(pat) if pat.subpat.is_some()));
+        assert!(matches!(&pats[14], Pat::Path(_)));
 
-impl std::fmt::Debug for Attribute {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        f.debug_struct("Attribute")
-            .field("name", &self.name)
-            .field("tree", &"TokenIter {...}")
-            .finish()
+        let bindings = pats[12].bindings();
+        let bindings = bindings.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(bindings, ["x", "y", "c", "d"]);
+
+        let output = pats
+            .iter()
+            .map(|pat| pat.to_token_stream().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(output[6], "& mut (a , b)");
+        assert_eq!(output[7], "(a ,)");
+        assert_eq!(output[8], "(a)");
+        assert_eq!(output[12], "Foo { x , ref y , z : (c , d) , .. }");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::str::FromStr;
+    #[test]
+    fn test_tokeniter_parse_pat_paths() {
+        let mut input = TokenStream::from_str(concat!(
+            "(a, b): (u8, u8), Foo { x, .. }: Foo, Wrapper::<u8>(x): Wrapper<u8>, ",
+            "Foo { 0: a, 1: ref b }: Foo",
+        ))
+        .unwrap()
+        .into_token_iter();
+        let args = input.parse_punctuated(',', parse_fn_arg).unwrap();
+        assert_eq!(args.len(), 4);
+        match &args.items[1] {
+            FnArg::Typed(pat_type) => assert!(matches!(pat_type.pat, Pat::Struct(_))),
+            FnArg::Receiver(_) => panic!(),
+        }
+        match &args.items[2] {
+            FnArg::Typed(pat_type) => match &pat_type.pat {
+                Pat::TupleStruct(pat) => assert_eq!(pat.path.to_string(), "Wrapper :: < u8 >"),
+                _ => panic!(),
+            },
+            FnArg::Receiver(_) => panic!(),
+        }
+        match &args.items[3] {
+            FnArg::Typed(pat_type) => {
+                assert_eq!(pat_type.pat.bindings().len(), 2);
+                assert_eq!(
+                    pat_type.pat.to_token_stream().to_string(),
+                    "Foo { 0 : a , 1 : ref b }"
+                );
+            }
+            FnArg::Receiver(_) => panic!(),
+        }
+
+        let mut input = TokenStream::from_str("<T>::X, <T as Trait>::Y(a), Vec::<Option<u8>>::NEW")
+            .unwrap()
+            .into_token_iter();
+        let pats = input
+            .parse_punctuated(',', TokenIterExt::parse_pat)
+            .unwrap();
+        assert!(matches!(&pats.items[0], Pat::Path(tokens) if tokens.to_string() == "< T >:: X"));
+        assert!(matches!(&pats.items[1], Pat::TupleStruct(_)));
+        assert!(matches!(&pats.items[2], Pat::Path(_)));
+
+        let mut input = TokenStream::from_str("n @ Foo { a: x, b }")
+            .unwrap()
+            .into_token_iter();
+        let pat = input.parse_pat().unwrap();
+        assert_eq!(pat.bindings().len(), 3);
+        assert_eq!(pat.to_token_stream().to_string(), "n @ Foo { a : x , b }");
+    }
 
     #[test]
     fn test_tokeniter_parse_path() {
@@ -268,6 +4794,15 @@ mod tests {
         let mut input = TokenStream::from_str("(u8, )").unwrap().into_token_iter();
         assert_eq!(input.parse_path().unwrap().0, "(u8,)");
         assert!(input.next().is_none());
+
+        let mut input = TokenStream::from_str("Vec<u8> , Box<dyn Fn() -> u8>, x")
+            .unwrap()
+            .into_token_iter();
+        assert_eq!(input.parse_path().unwrap().0, "Vec<u8>");
+        input.expect_punct(',').unwrap();
+        assert_eq!(input.parse_path().unwrap().0, "Box<dyn Fn()->u8>");
+        input.expect_punct(',').unwrap();
+        assert_eq!(input.parse_path().unwrap().0, "x");
     }
 
     #[test]
@@ -1,31 +1,1413 @@
 //! Miscellaneous functions.
 
-use crate::traits::{LiteralExt as _, TokenIterExt as _};
-use crate::ty::Attribute;
-use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use crate::macros::{push_group, push_ident, push_lifetime, push_punct};
+use crate::traits::{
+    LiteralExt as _, Parse, ToTokens, TokenIterExt, TokenStreamExt as _, TokenTreeExt as _,
+};
+use crate::ty::{Attribute, CfgAttr, CfgPredicate, DocComment, DocLine, IntType, Repr, TokenIter};
+use proc_macro::{Delimiter, Ident, Literal, Spacing, Span, TokenStream, TokenTree};
+
+/// Keywords which must be written as raw identifiers when used as names.
+///
+/// This includes keywords reserved in any edition, e.g. `gen` is only reserved since the 2024
+/// edition. Raw identifiers are valid in every edition that supports procedural macros, so
+/// escaping them unconditionally is always accepted.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// A fluent builder for [`TokenStream`]s.
+///
+/// All tokens are created with the builder's current span, which defaults to
+/// [`Span::call_site`]. Use [`TokenBuilder::with_span`] to change it for a sequence of tokens.
+///
+/// # Example
+///
+/// ```ignore
+/// use myn::utils::TokenBuilder;
+/// use proc_macro::Delimiter;
+///
+/// // impl ::std::default::Default for Foo { }
+/// let output = TokenBuilder::new()
+///     .ident("impl")
+///     .punct_joint("::")
+///     .ident("std")
+///     .punct_joint("::")
+///     .ident("default")
+///     .punct_joint("::")
+///     .ident("Default")
+///     .ident("for")
+///     .extend(&name)
+///     .group(Delimiter::Brace, |b| b)
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct TokenBuilder {
+    tokens: TokenStream,
+    span: Span,
+}
+
+impl TokenBuilder {
+    /// Create an empty builder using [`Span::call_site`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::new_spanned(Span::call_site())
+    }
+
+    /// Create an empty builder using the given span.
+    #[must_use]
+    pub fn new_spanned(span: Span) -> Self {
+        Self {
+            tokens: TokenStream::new(),
+            span,
+        }
+    }
+
+    /// Append an identifier.
+    ///
+    /// Raw identifiers like `r#type` are supported.
+    #[must_use]
+    pub fn ident(mut self, ident: &str) -> Self {
+        push_ident(&mut self.tokens, self.span, ident);
+        self
+    }
+
+    /// Append a lifetime, e.g. `'a`.
+    #[must_use]
+    pub fn lifetime(mut self, name: &str) -> Self {
+        push_lifetime(&mut self.tokens, self.span, name);
+        self
+    }
+
+    /// Append a single punctuation character with [`Spacing::Alone`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ch` is not valid punctuation.
+    ///
+    /// [`Spacing::Alone`]: proc_macro::Spacing::Alone
+    #[must_use]
+    pub fn punct(mut self, ch: char) -> Self {
+        push_punct(&mut self.tokens, self.span, ch.encode_utf8(&mut [0; 4]));
+        self
+    }
+
+    /// Append a multi-character operator, like `::`, `->`, or `=>`.
+    ///
+    /// All characters are [`Spacing::Joint`] except the last, which is [`Spacing::Alone`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is empty or contains a character that is not valid punctuation.
+    ///
+    /// [`Spacing::Alone`]: proc_macro::Spacing::Alone
+    /// [`Spacing::Joint`]: proc_macro::Spacing::Joint
+    #[must_use]
+    pub fn punct_joint(mut self, op: &str) -> Self {
+        assert!(!op.is_empty(), "Empty operator");
+        push_punct(&mut self.tokens, self.span, op);
+        self
+    }
+
+    /// Append a string literal.
+    #[must_use]
+    pub fn lit_str(self, value: &str) -> Self {
+        self.lit(Literal::string(value))
+    }
+
+    /// Append a literal, replacing its span with the builder's span.
+    #[must_use]
+    pub fn lit(mut self, mut lit: Literal) -> Self {
+        lit.set_span(self.span);
+        self.tokens.extend([TokenTree::Literal(lit)]);
+        self
+    }
+
+    /// Append a group with the given delimiter.
+    ///
+    /// The closure receives a new builder with the same span for building the group contents.
+    #[must_use]
+    pub fn group<F>(mut self, delim: Delimiter, f: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let inner = f(Self::new_spanned(self.span)).build();
+        push_group(&mut self.tokens, self.span, delim, inner);
+        self
+    }
+
+    /// Append any tokens, keeping their original spans.
+    #[must_use]
+    pub fn extend<T: ToTokens>(mut self, tokens: T) -> Self {
+        tokens.to_tokens(&mut self.tokens);
+        self
+    }
+
+    /// Append tokens built with a different span.
+    #[must_use]
+    pub fn with_span<F>(mut self, span: Span, f: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let inner = f(Self::new_spanned(span)).build();
+        self.tokens.extend(inner);
+        self
+    }
+
+    /// Finish building and return the [`TokenStream`].
+    #[must_use]
+    pub fn build(self) -> TokenStream {
+        self.tokens
+    }
+}
+
+impl Default for TokenBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create an identifier with the given span.
+///
+/// Keywords are automatically created as raw identifiers, e.g. `type` becomes `r#type`. An
+/// existing `r#` prefix is accepted. Path keywords like `self` and `crate` cannot be raw, and are
+/// created as-is.
+///
+/// Keywords from every edition are escaped, so `gen` becomes `r#gen` even though it is only
+/// reserved in the 2024 edition. Both spellings name the same identifier in earlier editions.
+///
+/// Validation uses the compiler's tokenizer, so identifiers follow the same Unicode rules
+/// (`XID_Start` and `XID_Continue`) as hand-written code.
+///
+/// See also [`format_ident!`] for creating identifiers from format strings.
+///
+/// # Errors
+///
+/// Returns a compiler error if the string is not a valid identifier. The error should be inserted
+/// into the `proc_macro` stream.
+///
+/// [`format_ident!`]: crate::format_ident
+pub fn new_ident(name: &str, span: Span) -> Result<Ident, TokenStream> {
+    let raw = name.strip_prefix("r#");
+    let ident = raw.unwrap_or(name);
+
+    // Let the tokenizer decide, rather than approximating the `XID_Start` and `XID_Continue`
+    // character classes. This rejects anything that `Ident::new` would panic on.
+    let valid = !ident.starts_with("r#")
+        && ident.parse::<TokenStream>().map_or(false, |stream| {
+            let mut trees = stream.into_iter();
+            match (trees.next(), trees.next()) {
+                (Some(TokenTree::Ident(parsed)), None) => parsed.to_string() == ident,
+                _ => false,
+            }
+        });
+    if !valid || (raw.is_some() && ident == "_") {
+        return Err(spanned_error(format!("Invalid identifier `{name}`"), span));
+    }
+
+    if KEYWORDS.contains(&ident) {
+        Ok(Ident::new_raw(ident, span))
+    } else {
+        Ok(Ident::new(ident, span))
+    }
+}
+
+/// Identifier case styles.
+///
+/// The names accepted by [`Case::from_name`] match the `rename_all` conventions used by `serde`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Case {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+}
+
+impl Case {
+    const NAMES: [(&'static str, Case); 8] = [
+        ("lowercase", Case::Lower),
+        ("UPPERCASE", Case::Upper),
+        ("PascalCase", Case::Pascal),
+        ("camelCase", Case::Camel),
+        ("snake_case", Case::Snake),
+        ("SCREAMING_SNAKE_CASE", Case::ScreamingSnake),
+        ("kebab-case", Case::Kebab),
+        ("SCREAMING-KEBAB-CASE", Case::ScreamingKebab),
+    ];
+
+    /// Get the case style by name, e.g. `"kebab-case"`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(case_name, _)| *case_name == name)
+            .map(|(_, case)| *case)
+    }
+
+    /// Get the name of this case style, e.g. `"kebab-case"`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, case)| *case == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+}
+
+/// Convert a name to the given case style.
+///
+/// The name is split into words at `_`, `-`, whitespace, and changes in letter case. Acronyms are
+/// kept together, so `HTTPServer` is split into `HTTP` and `Server`. A leading `r#` is removed.
+#[must_use]
+pub fn to_case(name: &str, case: Case) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let words = split_words(name);
+    let mut output = String::with_capacity(name.len());
+    let separator = match case {
+        Case::Snake | Case::ScreamingSnake => Some('_'),
+        Case::Kebab | Case::ScreamingKebab => Some('-'),
+        _ => None,
+    };
+
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            output.extend(separator);
+        }
+
+        match case {
+            Case::Lower | Case::Snake | Case::Kebab => {
+                output.extend(word.chars().flat_map(char::to_lowercase));
+            }
+            Case::Upper | Case::ScreamingSnake | Case::ScreamingKebab => {
+                output.extend(word.chars().flat_map(char::to_uppercase));
+            }
+            Case::Camel if i == 0 => {
+                output.extend(word.chars().flat_map(char::to_lowercase));
+            }
+            Case::Pascal | Case::Camel => {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    output.extend(first.to_uppercase());
+                    output.extend(chars.flat_map(char::to_lowercase));
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Convert an identifier to the given case style, returning the string and the identifier's span.
+///
+/// Use [`new_ident`] to turn the result back into an identifier, or [`Literal::string`] for a
+/// string literal.
+///
+/// [`Literal::string`]: proc_macro::Literal::string
+#[must_use]
+pub fn ident_to_case(ident: &Ident, case: Case) -> (String, Span) {
+    (to_case(&ident.to_string(), case), ident.span())
+}
+
+/// Split a name into words for case conversion.
+fn split_words(name: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut chars = name.char_indices().peekable();
+    let mut prev: Option<char> = None;
+
+    while let Some((i, ch)) = chars.next() {
+        if ch == '_' || ch == '-' || ch.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(&name[start..i]);
+            }
+            prev = None;
+            continue;
+        }
+
+        if let (Some(word_start), Some(prev)) = (start, prev) {
+            let next_lower = chars.peek().map_or(false, |(_, next)| next.is_lowercase());
+            let boundary = ch.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next_lower));
+
+            if boundary {
+                words.push(&name[word_start..i]);
+                start = Some(i);
+            }
+        }
+
+        start.get_or_insert(i);
+        prev = Some(ch);
+    }
+
+    if let Some(start) = start {
+        words.push(&name[start..]);
+    }
+
+    words
+}
+
+/// Get the case style from an attribute of the form `#[name = "..."]`, e.g.
+/// `#[rename_all = "kebab-case"]`.
+///
+/// Returns `Ok(None)` when the attribute is not present. When the attribute is repeated, the last
+/// one wins.
+///
+/// # Errors
+///
+/// Returns a compiler error if the attribute value is not a known case style. The error should be
+/// inserted into the `proc_macro` stream.
+pub fn get_attr_case(attrs: &[Attribute], name: &str) -> Result<Option<Case>, TokenStream> {
+    let mut result = None;
+
+    for attr in attrs.iter().filter(|attr| attr.name.to_string() == name) {
+        let mut tree = attr.tree.clone();
+        tree.expect_punct('=')?;
+        let lit = tree.try_lit()?;
+        tree.expect_end()?;
+        let value = lit.as_string()?;
+
+        let case = Case::from_name(&value).ok_or_else(|| {
+            let expected = Case::NAMES
+                .iter()
+                .map(|(name, _)| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            spanned_error(
+                format!("Unknown case `{value}`, expected one of {expected}"),
+                lit.span(),
+            )
+        })?;
+        result = Some(case);
+    }
+
+    Ok(result)
+}
+
+/// Render the Markdown commonly found in doc comments as plain text.
+///
+/// This is intended for `--help` output and error messages, e.g. with the text from
+/// [`DocComment::text`]. Only a small subset of Markdown is supported:
+///
+/// - Inline code, emphasis, and strikethrough markers are removed. Emphasis markers must be
+///   paired, opening before non-whitespace and closing after it, so `a*b` is kept as-is.
+/// - Links and images are replaced with their text. This covers inline links `[text](url)`,
+///   reference links `[text][ref]`, and shortcut links `[text]` when `text` is a code span (an
+///   intra-doc link like ``[`Foo`]``) or has a reference definition. Other brackets, like
+///   `foo[0]`, are kept. Autolinks like `<https://example.com>` are replaced with the URL. Link
+///   reference definitions are removed.
+/// - Heading markers and blockquote markers are removed.
+/// - List items are kept, with `*` and `+` bullets replaced by `-`.
+/// - Fenced code blocks are indented by four spaces, and hidden lines (starting with `# `) in Rust
+///   code blocks are removed.
+///
+/// Everything else, including line breaks, is kept as-is.
+#[must_use]
+pub fn markdown_to_text(markdown: &str) -> String {
+    let mut lines = vec![];
+    let mut fence: Option<(String, bool)> = None;
+    let refs = markdown
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| is_link_definition(line))
+        .filter_map(|line| line.find("]:").map(|end| line[1..end].to_lowercase()))
+        .collect::<Vec<_>>();
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some((marker, is_rust)) = &fence {
+            if trimmed.starts_with(marker.as_str()) && trimmed[marker.len()..].trim().is_empty() {
+                fence = None;
+            } else if !(*is_rust && (trimmed == "#" || trimmed.starts_with("# "))) {
+                lines.push(format!("    {line}").trim_end().to_string());
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let ch = trimmed.chars().next().unwrap_or('`');
+            let len = trimmed.chars().take_while(|&c| c == ch).count();
+            let lang = trimmed[len..].trim();
+            let is_rust = lang.is_empty()
+                || lang.split(',').any(|tag| {
+                    ["rust", "ignore", "no_run", "should_panic", "compile_fail"]
+                        .contains(&tag.trim())
+                });
+            fence = Some((ch.to_string().repeat(len), is_rust));
+            continue;
+        }
+
+        if is_link_definition(trimmed) {
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let mut rest = trimmed;
+        let mut prefix = String::from(indent);
+
+        // Blockquotes
+        while let Some(inner) = rest.strip_prefix('>') {
+            rest = inner.trim_start();
+        }
+
+        // Headings
+        let hashes = rest.chars().take_while(|&ch| ch == '#').count();
+        let heading = &rest[hashes..];
+        if (1..=6).contains(&hashes) && (heading.is_empty() || heading.starts_with(' ')) {
+            rest = heading.trim();
+        }
+
+        // List items
+        if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| rest.strip_prefix(bullet))
+        {
+            prefix.push_str("- ");
+            rest = item;
+        } else {
+            let digits = rest.chars().take_while(char::is_ascii_digit).count();
+            if digits > 0 && (rest[digits..].starts_with(". ") || rest[digits..].starts_with(") "))
+            {
+                prefix.push_str(&rest[..digits + 2]);
+                rest = &rest[digits + 2..];
+            }
+        }
+
+        lines.push(
+            format!("{prefix}{}", render_inline(rest, &refs))
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    lines.join("\n")
+}
+
+/// Check for a link reference definition, like `[foo]: https://example.com`.
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[')
+        && line
+            .find("]:")
+            .map_or(false, |end| !line[1..end].contains(']'))
+}
+
+/// Render inline Markdown as plain text.
+///
+/// `refs` contains the lowercase labels of all link reference definitions.
+fn render_inline(text: &str, refs: &[String]) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            '\\' if chars.get(i + 1).map_or(false, char::is_ascii_punctuation) => {
+                output.push(chars[i + 1]);
+                i += 2;
+            }
+            '`' => {
+                let run = count_run(&chars, i);
+                if let Some(end) = find_run(&chars, i + run, '`', run) {
+                    let code = chars[i + run..end].iter().collect::<String>();
+                    let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                        Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+                        _ => code,
+                    };
+                    output.push_str(&code);
+                    i = end + run;
+                } else {
+                    output.extend(&chars[i..i + run]);
+                    i += run;
+                }
+            }
+            '!' | '[' => {
+                let start = if ch == '!' { i + 1 } else { i };
+                match parse_link(&chars, start, refs) {
+                    Some((inner, end)) if chars.get(start) == Some(&'[') => {
+                        output.push_str(&render_inline(&inner, refs));
+                        i = end;
+                    }
+                    _ => {
+                        output.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            '<' => {
+                let end = chars[i..].iter().position(|&c| c == '>').map(|end| i + end);
+                let inner = end.map(|end| chars[i + 1..end].iter().collect::<String>());
+                match (end, inner) {
+                    (Some(end), Some(inner))
+                        if inner.contains("://") && !inner.contains(char::is_whitespace) =>
+                    {
+                        output.push_str(&inner);
+                        i = end + 1;
+                    }
+                    _ => {
+                        output.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            '*' | '_' | '~' => {
+                let run = count_run(&chars, i);
+                let closing = if can_open_emphasis(&chars, i, run) {
+                    find_closing_emphasis(&chars, i + run, ch, run)
+                } else {
+                    None
+                };
+
+                if let Some(end) = closing {
+                    let inner = chars[i + run..end].iter().collect::<String>();
+                    output.push_str(&render_inline(&inner, refs));
+                    i = end + run;
+                } else {
+                    output.extend(&chars[i..i + run]);
+                    i += run;
+                }
+            }
+            _ => {
+                output.push(ch);
+                i += 1;
+            }
+        }
+    }
+
+    output
+}
+
+/// Parse a link starting with `[` at `start`, returning its text and the index following it.
+///
+/// Shortcut links like `[text]` are only links when the text is a code span (an intra-doc link) or
+/// has a reference definition.
+fn parse_link(chars: &[char], start: usize, refs: &[String]) -> Option<(String, usize)> {
+    if chars.get(start) != Some(&'[') {
+        return None;
+    }
+    let end = find_closing(chars, start, '[', ']')?;
+    let inner = chars[start + 1..end].iter().collect::<String>();
+
+    // Inline links and full or collapsed reference links.
+    let closing = match chars.get(end + 1) {
+        Some('(') => find_closing(chars, end + 1, '(', ')'),
+        Some('[') => find_closing(chars, end + 1, '[', ']'),
+        _ => None,
+    };
+    if let Some(closing) = closing {
+        return Some((inner, closing + 1));
+    }
+
+    // Shortcut links.
+    let trimmed = inner.trim();
+    let is_code = trimmed.len() > 1
+        && trimmed.starts_with('`')
+        && trimmed.ends_with('`')
+        && !trimmed.trim_matches('`').contains('`');
+    if is_code || refs.contains(&trimmed.to_lowercase()) {
+        Some((inner, end + 1))
+    } else {
+        None
+    }
+}
+
+/// Check if the delimiter run at `start` can open emphasis.
+///
+/// The run must be left-flanking: followed by non-whitespace. Underscores also cannot open
+/// within a word, like in `snake_case`, and strikethrough requires exactly two tildes.
+fn can_open_emphasis(chars: &[char], start: usize, run: usize) -> bool {
+    let before = start.checked_sub(1).map(|j| chars[j]);
+    let after = chars.get(start + run).copied();
+
+    let left_flanking = after.map_or(false, |c| !c.is_whitespace());
+    match chars[start] {
+        '_' => left_flanking && !before.map_or(false, char::is_alphanumeric),
+        '~' => left_flanking && run == 2,
+        _ => left_flanking,
+    }
+}
+
+/// Find a delimiter run of exactly `run` characters which can close emphasis.
+///
+/// The run must be right-flanking: preceded by non-whitespace. Underscores also cannot close
+/// within a word.
+fn find_closing_emphasis(chars: &[char], start: usize, ch: char, run: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == ch => {
+                let len = count_run(chars, i);
+                let before = chars[i - 1];
+                let after = chars.get(i + len).copied();
+                let right_flanking = i > start && !before.is_whitespace();
+                let closes =
+                    right_flanking && (ch != '_' || !after.map_or(false, char::is_alphanumeric));
+                if closes && len == run {
+                    return Some(i);
+                }
+                i += len;
+            }
+            _ => i += 1,
+        }
+    }
+
+    None
+}
+
+/// Count the number of repeated characters starting at `start`.
+fn count_run(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .take_while(|&&ch| ch == chars[start])
+        .count()
+}
+
+/// Find the start of a run of exactly `len` repeated characters.
+fn find_run(chars: &[char], start: usize, ch: char, len: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == ch {
+            let run = count_run(chars, i);
+            if run == len {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+
+    None
+}
+
+/// Find the closing bracket matching the opening bracket at `start`.
+fn find_closing(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '`' => {
+                // Brackets in code spans do not count.
+                let run = count_run(chars, i);
+                i = find_run(chars, i + run, '`', run).map_or(i + run - 1, |end| end + run - 1);
+            }
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Get the `#[cfg(...)]` attributes.
+///
+/// These can be forwarded onto generated items with [`quote!`] to make them conditional on the
+/// same configuration as the input.
+///
+/// [`quote!`]: crate::quote
+#[must_use]
+pub fn get_cfg_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.name.to_string() == "cfg")
+        .collect()
+}
+
+/// Parse the predicate from a `#[cfg(...)]` attribute.
+///
+/// # Errors
+///
+/// Returns a compiler error if parsing fails. The error should be inserted into the
+/// `proc_macro` stream.
+pub fn parse_cfg(attr: &Attribute) -> Result<CfgPredicate, TokenStream> {
+    let mut tree = attr.tree.clone();
+    let predicate = tree
+        .expect_group(Delimiter::Parenthesis)?
+        .parse_all(TokenIterExt::parse_cfg_predicate)?;
+    tree.expect_end()?;
+
+    Ok(predicate)
+}
+
+/// Parse a `#[cfg_attr(predicate, attributes...)]` attribute.
+///
+/// # Errors
+///
+/// Returns a compiler error if parsing fails. The error should be inserted into the
+/// `proc_macro` stream.
+pub fn parse_cfg_attr(attr: &Attribute) -> Result<CfgAttr, TokenStream> {
+    let mut tree = attr.tree.clone();
+    let mut group = tree.expect_group(Delimiter::Parenthesis)?;
+    let predicate = group.parse_cfg_predicate()?;
+    group.expect_punct(',')?;
+
+    let attrs = group.parse_punctuated(',', |iter| {
+        let name = iter.try_ident()?;
+        let mut inner = TokenStream::new();
+        while let Some(tree) = iter.next_if(|tree| !is_punct(tree, ',')) {
+            inner.extend([tree]);
+        }
+
+        Ok(Attribute {
+            name,
+            tree: inner.into_token_iter(),
+            style: attr.style,
+        })
+    })?;
+
+    Ok(CfgAttr {
+        span: attr.name.span(),
+        style: attr.style,
+        predicate,
+        attrs: attrs.items,
+    })
+}
+
+/// Replace all `#[cfg_attr(...)]` attributes with the attributes they contain.
+///
+/// Nested `cfg_attr` attributes are also expanded. The predicates are discarded, so this is useful
+/// for discovering helper attributes regardless of configuration. Use [`parse_cfg_attr`] to keep
+/// the predicates.
+///
+/// # Errors
+///
+/// Returns a compiler error if parsing fails. The error should be inserted into the
+/// `proc_macro` stream.
+pub fn expand_cfg_attrs(attrs: &[Attribute]) -> Result<Vec<Attribute>, TokenStream> {
+    let mut expanded = vec![];
+
+    for attr in attrs {
+        if attr.name.to_string() == "cfg_attr" {
+            let cfg_attr = parse_cfg_attr(attr)?;
+            expanded.extend(expand_cfg_attrs(&cfg_attr.attrs)?);
+        } else {
+            expanded.push(attr.clone());
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Parse all `#[repr(...)]` attributes into a [`Repr`].
+///
+/// Multiple `repr` attributes are combined, as the compiler does.
+///
+/// # Errors
+///
+/// Returns a compiler error if parsing fails or the representation hints conflict. The error
+/// should be inserted into the `proc_macro` stream.
+pub fn parse_repr(attrs: &[Attribute]) -> Result<Repr, TokenStream> {
+    let mut repr = Repr::default();
+
+    for attr in attrs.iter().filter(|attr| attr.name.to_string() == "repr") {
+        let mut tree = attr.tree.clone();
+        let mut group = tree.expect_group(Delimiter::Parenthesis)?;
+        tree.expect_end()?;
+
+        while group.peek().is_some() {
+            let ident = group.try_ident()?;
+            let span = ident.span();
+            let name = ident.to_string();
+
+            let conflict = match name.as_str() {
+                "C" => repr.c.replace(span).is_some(),
+                "transparent" => repr.transparent.replace(span).is_some(),
+                "packed" => {
+                    let align = if let Some(TokenTree::Group(_)) = group.peek() {
+                        parse_repr_alignment(&mut group)?
+                    } else {
+                        1
+                    };
+                    repr.packed.replace((align, span)).is_some()
+                }
+                "align" => {
+                    let align = parse_repr_alignment(&mut group)?;
+                    repr.align.replace((align, span)).is_some()
+                }
+                _ => {
+                    let int = IntType::from_name(&name).ok_or_else(|| {
+                        spanned_error(format!("Unknown representation hint `{name}`"), span)
+                    })?;
+                    repr.int.replace((int, span)).is_some()
+                }
+            };
+            if conflict {
+                return Err(spanned_error("Conflicting representation hints", span));
+            }
+
+            if group.peek().is_some() {
+                group.expect_punct(',')?;
+            }
+        }
+    }
+
+    if let Some(span) = repr.transparent {
+        let has_other =
+            repr.int.is_some() || repr.c.is_some() || repr.packed.is_some() || repr.align.is_some();
+        if has_other {
+            return Err(spanned_error(
+                "`transparent` cannot be combined with other representation hints",
+                span,
+            ));
+        }
+    }
+    if let (Some((_, span)), Some(_)) = (repr.packed, repr.align) {
+        return Err(spanned_error(
+            "`packed` and `align` representation hints conflict",
+            span,
+        ));
+    }
+
+    Ok(repr)
+}
+
+/// Parse the `(N)` alignment for `packed` and `align` representation hints.
+fn parse_repr_alignment(group: &mut TokenIter) -> Result<u32, TokenStream> {
+    let mut inner = group.expect_group(Delimiter::Parenthesis)?;
+    let lit = inner.try_lit()?;
+    let align = lit.as_int()?;
+
+    match u32::try_from(align) {
+        Ok(align) if align.is_power_of_two() && inner.peek().is_none() => Ok(align),
+        _ => Err(spanned_error(
+            "Alignment must be a power of two",
+            lit.span(),
+        )),
+    }
+}
+
+/// Evaluate the discriminants of the C-like enum `name`.
+///
+/// Each variant is given as its name and optional explicit discriminant expression (the tokens
+/// after `=`). Variants without an explicit discriminant are one more than the previous variant,
+/// starting at zero.
+///
+/// Returns one value per variant. When an explicit discriminant cannot be evaluated by
+/// [`eval_discriminant`], its value and the values of the implicit discriminants following it are
+/// `None`. These can be emitted as `Enum::Variant as T` casts instead.
+///
+/// Expressions are evaluated as the integer type from the `repr`, or `isize` when there is none.
+///
+/// # Errors
+///
+/// Returns a compiler error if a discriminant overflows or is invalid. The error should be
+/// inserted into the `proc_macro` stream.
+pub fn eval_discriminants(
+    name: &Ident,
+    variants: &[(Ident, Option<TokenStream>)],
+    repr: &Repr,
+) -> Result<Vec<Option<i128>>, TokenStream> {
+    let int = repr.int.map_or(IntType::Isize, |(int, _)| int);
+    let enum_name = name.to_string();
+    let mut known = vec![];
+    let mut values: Vec<Option<i128>> = vec![];
+
+    for (name, expr) in variants {
+        let scope = Scope {
+            name: &enum_name,
+            variants: &known,
+        };
+        let value = match (expr, values.last().copied()) {
+            (Some(expr), _) => match eval_expr(expr.clone(), &scope, int) {
+                Ok(value) => Some(value),
+                Err(EvalError::Unsupported(_)) => None,
+                Err(EvalError::Error(err)) => return Err(err),
+            },
+            (None, None) => Some(0),
+            (None, Some(None)) => None,
+            (None, Some(Some(prev))) => Some(
+                prev.checked_add(1)
+                    .filter(|value| *value <= int.max())
+                    .ok_or_else(|| spanned_error("Enum discriminant overflowed", name.span()))?,
+            ),
+        };
+
+        if let Some(value) = value {
+            if value < int.min() || value > int.max() {
+                let span = expr
+                    .as_ref()
+                    .and_then(|expr| expr.clone().into_iter().next())
+                    .map_or_else(|| name.span(), |tree| Some(tree).as_span());
+
+                return Err(spanned_error(
+                    format!(
+                        "Discriminant value `{value}` is out of range for `{}`",
+                        int.name()
+                    ),
+                    span,
+                ));
+            }
+            known.push((name.to_string(), value));
+        }
+
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// Evaluate a constant discriminant expression of the integer type `int`.
+///
+/// Supports integer literals, parentheses, unary `-` and `!`, the binary operators
+/// `* / % + - << >> & ^ |` with Rust precedence, `as` casts to integer types, and references to
+/// earlier variants of the enum `name` (e.g. `Self::A` or `Enum::A`) given as name-value pairs.
+///
+/// Operators are evaluated at the width and signedness of `int`, so `!0` is `255` for `u8`. As in
+/// a constant context, arithmetic overflow is an error. The operand of an `as` cast has the type
+/// of its literal suffix, or `i32` when there is none.
+///
+/// # Errors
+///
+/// Returns a compiler error if the expression is not supported, or if it overflows. The error
+/// should be inserted into the `proc_macro` stream.
+pub fn eval_discriminant(
+    expr: TokenStream,
+    name: &str,
+    variants: &[(String, i128)],
+    int: IntType,
+) -> Result<i128, TokenStream> {
+    let scope = Scope { name, variants };
+    eval_expr(expr, &scope, int).map_err(|err| match err {
+        EvalError::Unsupported(span) => {
+            spanned_error("Unsupported expression in discriminant", span)
+        }
+        EvalError::Error(err) => err,
+    })
+}
+
+/// The enum name and the earlier variants which a discriminant can refer to.
+struct Scope<'a> {
+    name: &'a str,
+    variants: &'a [(String, i128)],
+}
+
+enum EvalError {
+    /// The expression cannot be evaluated by this crate.
+    Unsupported(Span),
+
+    /// The expression is invalid.
+    Error(TokenStream),
+}
+
+fn overflow(span: Span) -> EvalError {
+    EvalError::Error(spanned_error("Overflow evaluating discriminant", span))
+}
+
+fn eval_expr(expr: TokenStream, scope: &Scope, int: IntType) -> Result<i128, EvalError> {
+    let mut iter = expr.into_token_iter();
+    let value = eval_binary(&mut iter, scope, int, 0)?;
+
+    match iter.next() {
+        None => Ok(value),
+        tree => Err(EvalError::Unsupported(tree.as_span())),
+    }
+}
+
+/// Binary operators with their precedence, from lowest to highest.
+const BINARY_OPS: [(&str, u8); 10] = [
+    ("|", 1),
+    ("^", 2),
+    ("&", 3),
+    ("<<", 4),
+    (">>", 4),
+    ("+", 5),
+    ("-", 5),
+    ("*", 6),
+    ("/", 6),
+    ("%", 6),
+];
+
+/// Precedence climbing over binary operators.
+fn eval_binary(
+    iter: &mut TokenIter,
+    scope: &Scope,
+    int: IntType,
+    min_prec: u8,
+) -> Result<i128, EvalError> {
+    let mut lhs = eval_cast(iter, scope, int)?;
+
+    loop {
+        let (op, prec, span) = match peek_binary_op(iter) {
+            Some((op, prec, span)) if prec > min_prec => (op, prec, span),
+            _ => return Ok(lhs),
+        };
+        for _ in 0..op.len() {
+            iter.next();
+        }
+
+        let rhs = eval_binary(iter, scope, int, prec)?;
+        let result = match op {
+            "|" => Some(lhs | rhs),
+            "^" => Some(lhs ^ rhs),
+            "&" => Some(lhs & rhs),
+            // Shifts only overflow when the shift amount is too large. Bits shifted out of the
+            // type are discarded.
+            "<<" => shift_amount(rhs, int).map(|rhs| cast_int(lhs.wrapping_shl(rhs), int)),
+            ">>" => shift_amount(rhs, int).map(|rhs| lhs >> rhs),
+            "+" => lhs.checked_add(rhs),
+            "-" => lhs.checked_sub(rhs),
+            "*" => lhs.checked_mul(rhs),
+            "/" => lhs.checked_div(rhs),
+            _ => lhs.checked_rem(rhs),
+        };
+
+        lhs = result
+            .filter(|value| (int.min()..=int.max()).contains(value))
+            .ok_or_else(|| overflow(span))?;
+    }
+}
+
+/// Check that a shift amount is less than the number of bits in `int`.
+fn shift_amount(rhs: i128, int: IntType) -> Option<u32> {
+    u32::try_from(rhs).ok().filter(|rhs| *rhs < int.bits())
+}
+
+/// Peek the next binary operator without consuming it.
+fn peek_binary_op(iter: &TokenIter) -> Option<(&'static str, u8, Span)> {
+    let mut lookahead = iter.fork();
+    let first = match lookahead.next() {
+        Some(TokenTree::Punct(punct)) => punct,
+        _ => return None,
+    };
+    let span = first.span();
+    let second = match (first.spacing(), lookahead.next()) {
+        (Spacing::Joint, Some(TokenTree::Punct(punct))) => Some(punct.as_char()),
+        _ => None,
+    };
+
+    let op = match (first.as_char(), second) {
+        ('<', Some('<')) => "<<",
+        ('>', Some('>')) => ">>",
+        // Compound operators like `&&` and `+=` are not supported.
+        (_, Some(_)) => return None,
+        (ch, None) => BINARY_OPS
+            .iter()
+            .map(|(op, _)| *op)
+            .find(|op| op.len() == 1 && op.starts_with(ch))?,
+    };
+    let prec = BINARY_OPS.iter().find(|(name, _)| *name == op)?.1;
+
+    Some((op, prec, span))
+}
+
+/// Evaluate `as` casts.
+fn eval_cast(iter: &mut TokenIter, scope: &Scope, int: IntType) -> Result<i128, EvalError> {
+    // The operand of a cast is not inferred from the enum, so find its own type first.
+    let int = cast_operand_type(iter).unwrap_or(int);
+    let mut value = eval_unary(iter, scope, int)?;
+
+    while let Some(TokenTree::Ident(ident)) = iter.peek() {
+        if ident.to_string() != "as" {
+            break;
+        }
+        iter.next();
+
+        let ty = iter
+            .try_ident()
+            .map_err(|_| EvalError::Unsupported(iter.peek().cloned().as_span()))?;
+        let int =
+            IntType::from_name(&ty.to_string()).ok_or_else(|| EvalError::Unsupported(ty.span()))?;
+        value = cast_int(value, int);
+    }
+
+    Ok(value)
+}
+
+/// Get the type of the unary expression at the start of `iter` if it is followed by `as`.
+///
+/// This is the literal suffix, or `i32` for unsuffixed literals, following integer fallback.
+fn cast_operand_type(iter: &TokenIter) -> Option<IntType> {
+    let mut lookahead = iter.fork();
+    while lookahead.peek_op("-") || lookahead.peek_op("!") {
+        lookahead.next();
+    }
+
+    let suffix = match lookahead.next()? {
+        TokenTree::Literal(lit) => lit_suffix(&lit),
+        TokenTree::Group(_) => None,
+        TokenTree::Ident(_) => {
+            while lookahead.peek_op("::") {
+                lookahead.next();
+                lookahead.next();
+                lookahead.next();
+            }
+            None
+        }
+        TokenTree::Punct(_) => return None,
+    };
+
+    lookahead.peek_keyword(&["as"])?;
+    Some(suffix.unwrap_or(IntType::I32))
+}
+
+/// Get the integer type suffix of a literal, e.g. `u8` in `1_u8`.
+fn lit_suffix(lit: &Literal) -> Option<IntType> {
+    // Suffixes start with `i` or `u`, which are never digits, even in hex literals.
+    let string = lit.to_string();
+    let start = string.rfind(['i', 'u'])?;
+
+    IntType::from_name(&string[start..])
+}
+
+/// Evaluate unary operators.
+fn eval_unary(iter: &mut TokenIter, scope: &Scope, int: IntType) -> Result<i128, EvalError> {
+    match iter.peek() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '-' => {
+            let span = punct.span();
+            if !int.is_signed() {
+                return Err(EvalError::Error(spanned_error(
+                    format!("Cannot negate `{}`", int.name()),
+                    span,
+                )));
+            }
+            iter.next();
+
+            // `-128` is valid for `i8`, even though `128` is not.
+            let value = match iter.peek() {
+                Some(TokenTree::Literal(lit)) => {
+                    let lit = lit.clone();
+                    iter.next();
+                    eval_lit(&lit, int, int.max().saturating_add(1))?
+                }
+                _ => eval_unary(iter, scope, int)?,
+            };
+            value
+                .checked_neg()
+                .filter(|value| *value >= int.min())
+                .ok_or_else(|| overflow(span))
+        }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '!' => {
+            let span = punct.span();
+            iter.next();
+            let value = eval_unary(iter, scope, int)?;
+            match int {
+                IntType::U128 => Err(EvalError::Unsupported(span)),
+                _ if int.is_signed() => Ok(!value),
+                _ => Ok(int.max() - value),
+            }
+        }
+        _ => eval_primary(iter, scope, int),
+    }
+}
+
+/// Evaluate a literal of the integer type `int`, with a maximum value of `max`.
+fn eval_lit(lit: &Literal, int: IntType, max: i128) -> Result<i128, EvalError> {
+    let value = lit
+        .as_int()
+        .map_err(|_| EvalError::Unsupported(lit.span()))?;
+
+    match lit_suffix(lit) {
+        Some(suffix) if suffix != int => Err(EvalError::Error(spanned_error(
+            format!("Expected `{}`, found `{}`", int.name(), suffix.name()),
+            lit.span(),
+        ))),
+        _ if value > max => Err(EvalError::Error(spanned_error(
+            format!("Literal out of range for `{}`", int.name()),
+            lit.span(),
+        ))),
+        _ => Ok(value),
+    }
+}
+
+/// Evaluate literals, parenthesized expressions, and references to earlier variants.
+fn eval_primary(iter: &mut TokenIter, scope: &Scope, int: IntType) -> Result<i128, EvalError> {
+    match iter.next() {
+        Some(TokenTree::Literal(lit)) => eval_lit(&lit, int, int.max()),
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            eval_expr(group.stream(), scope, int)
+        }
+        Some(TokenTree::Ident(ident)) => {
+            let mut segments = vec![ident];
+            while iter.peek_op("::") {
+                iter.expect_op("::").map_err(EvalError::Error)?;
+                segments.push(iter.try_ident().map_err(EvalError::Error)?);
+            }
+
+            // Variants are only in scope through `Self::A` or `Enum::A`. A bare `A` or a path like
+            // `Other::A` is some other constant.
+            let span = segments[0].span();
+            let first = segments[0].to_string();
+            if segments.len() != 2 || (first != "Self" && first != scope.name) {
+                return Err(EvalError::Unsupported(span));
+            }
+
+            let last = segments[1].to_string();
+            scope
+                .variants
+                .iter()
+                .find(|(name, _)| *name == last)
+                .map(|(_, value)| *value)
+                .ok_or(EvalError::Unsupported(span))
+        }
+        tree => Err(EvalError::Unsupported(tree.as_span())),
+    }
+}
+
+/// Cast an integer with the same truncation and sign extension as Rust `as` casts.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_lossless
+)]
+fn cast_int(value: i128, int: IntType) -> i128 {
+    match int {
+        IntType::I8 => value as i8 as i128,
+        IntType::I16 => value as i16 as i128,
+        IntType::I32 => value as i32 as i128,
+        IntType::I64 | IntType::Isize => value as i64 as i128,
+        IntType::U8 => value as u8 as i128,
+        IntType::U16 => value as u16 as i128,
+        IntType::U32 => value as u32 as i128,
+        IntType::U64 | IntType::Usize => value as u64 as i128,
+        IntType::I128 | IntType::U128 => value,
+    }
+}
+
+fn is_punct(tree: &TokenTree, ch: char) -> bool {
+    matches!(tree, TokenTree::Punct(punct) if punct.as_char() == ch)
+}
+
+/// Parse an entire [`TokenStream`] as `T`.
+///
+/// This is a convenient entry point for function-like macros, e.g.
+/// `parse_stream::<MyGrammar>(input)`.
+///
+/// # Errors
+///
+/// Returns a compiler error if parsing fails or if any tokens remain after parsing. The error
+/// should be inserted into the `proc_macro` stream.
+pub fn parse_stream<T: Parse>(stream: TokenStream) -> Result<T, TokenStream> {
+    stream.into_token_iter().parse_all(TokenIterExt::parse)
+}
 
 /// Create a compiler error with the given span.
 pub fn spanned_error<S: AsRef<str>>(msg: S, span: Span) -> TokenStream {
-    let mut group = Group::new(
-        Delimiter::Parenthesis,
-        TokenTree::from(Literal::string(msg.as_ref())).into(),
-    );
-    group.set_span(span);
-
-    TokenStream::from_iter([
-        TokenTree::Ident(Ident::new("compile_error", span)),
-        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
-        TokenTree::Group(group),
-        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
-    ])
+    TokenBuilder::new_spanned(span)
+        .ident("compile_error")
+        .punct('!')
+        .group(Delimiter::Parenthesis, |b| b.lit_str(msg.as_ref()))
+        .punct(';')
+        .build()
 }
 
 /// Get a list of lines representing the doc comments.
+///
+/// The lines are returned exactly as written in the `#[doc]` attributes. See
+/// [`parse_doc_comment`] for normalized text.
 #[must_use]
 pub fn get_doc_comment(attrs: &[Attribute]) -> Vec<String> {
     get_attr_strings(attrs, "doc")
 }
 
+/// Parse doc comments into normalized text.
+///
+/// This handles both line (`///`, `//!`) and block (`/** */`, `/*! */`) doc comments, as well as
+/// explicit `#[doc = "..."]` attributes. Use [`TokenIterExt::parse_inner_attributes`] to get the
+/// attributes for inner doc comments.
+///
+/// The text is normalized similarly to `rustdoc`:
+///
+/// - Leading and trailing blank lines in block comments are removed, along with a leading `*` on
+///   every line when all lines have one.
+/// - Indentation common to all non-blank lines is removed.
+/// - Trailing whitespace is removed.
+///
+/// Doc attribute values which are not string literals, like `#[doc = include_str!("README.md")]`,
+/// are collected into [`DocComment::macros`].
+///
+/// [`TokenIterExt::parse_inner_attributes`]: crate::traits::TokenIterExt::parse_inner_attributes
+#[must_use]
+pub fn parse_doc_comment(attrs: &[Attribute]) -> DocComment {
+    let mut doc = DocComment::default();
+
+    for attr in attrs.iter().filter(|attr| attr.name.to_string() == "doc") {
+        let mut tree = attr.tree.clone();
+        match tree.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => (),
+            _ => continue,
+        }
+
+        let lit = if let Ok(lit) = tree.try_lit() {
+            lit
+        } else {
+            doc.macros.push(tree.collect());
+            continue;
+        };
+        let text = match lit.as_string() {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        let span = lit.span();
+
+        let mut lines = text.split('\n').map(str::trim_end).collect::<Vec<_>>();
+        if lines.len() > 1 {
+            // Block comments
+            if lines.first().map_or(false, |line| line.is_empty()) {
+                lines.remove(0);
+            }
+            if lines.last().map_or(false, |line| line.is_empty()) {
+                lines.pop();
+            }
+
+            let decorated = lines
+                .iter()
+                .all(|line| line.is_empty() || line.trim_start().starts_with('*'));
+            if decorated {
+                for line in &mut lines {
+                    *line = line.trim_start().strip_prefix('*').unwrap_or(line);
+                }
+            }
+        }
+
+        doc.lines.extend(lines.into_iter().map(|line| DocLine {
+            text: line.to_string(),
+            span,
+        }));
+    }
+
+    let indent = doc
+        .lines
+        .iter()
+        .filter(|line| !line.text.trim().is_empty())
+        .map(|line| line.text.len() - line.text.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    for line in &mut doc.lines {
+        line.text = line.text.get(indent..).unwrap_or_default().to_string();
+    }
+
+    doc
+}
+
 /// Get a list of strings matching the given attribute name.
 ///
 /// This only supports attributes of the form `#[name = "..."]`.
@@ -49,3 +1431,364 @@ pub fn get_attr_strings(attrs: &[Attribute], name: &str) -> Vec<String> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro::Spacing;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_token_builder() {
+        let output = TokenBuilder::new()
+            .ident("fn")
+            .ident("r#type")
+            .punct('<')
+            .lifetime("'a")
+            .punct('>')
+            .group(Delimiter::Parenthesis, |b| b)
+            .punct_joint("->")
+            .ident("u8")
+            .group(Delimiter::Brace, |b| b.lit(Literal::u8_suffixed(0)))
+            .build();
+
+        assert_eq!(output.to_string(), "fn r#type < 'a > () -> u8 { 0u8 }");
+    }
+
+    #[test]
+    fn test_to_case() {
+        let cases = [
+            ("foo_bar", Case::Pascal, "FooBar"),
+            ("r#type", Case::Pascal, "Type"),
+            ("FooBar", Case::Snake, "foo_bar"),
+            ("HTTPServer", Case::Kebab, "http-server"),
+            ("max_size2", Case::ScreamingSnake, "MAX_SIZE2"),
+            ("Vec3Field", Case::Camel, "vec3Field"),
+            ("some-value", Case::ScreamingKebab, "SOME-VALUE"),
+            ("FooBar", Case::Lower, "foobar"),
+            ("größe_wert", Case::Pascal, "GrößeWert"),
+            ("ÉtéJour", Case::Snake, "été_jour"),
+        ];
+
+        for (input, case, expected) in cases {
+            assert_eq!(to_case(input, case), expected, "{input} as {}", case.name());
+        }
+    }
+
+    #[test]
+    fn test_get_attr_case() {
+        let mut input = TokenStream::from_str(r#"#[rename_all = "kebab-case"] #[doc = "Foo"]"#)
+            .unwrap()
+            .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+        assert_eq!(
+            get_attr_case(&attrs, "rename_all").unwrap(),
+            Some(Case::Kebab)
+        );
+        assert_eq!(get_attr_case(&attrs, "rename").unwrap(), None);
+
+        let mut input = TokenStream::from_str(r#"#[rename_all = "Kebab"]"#)
+            .unwrap()
+            .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+        assert!(get_attr_case(&attrs, "rename_all").is_err());
+
+        let mut input = TokenStream::from_str(r#"#[rename_all = "kebab-case" extra]"#)
+            .unwrap()
+            .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+        let error = get_attr_case(&attrs, "rename_all").unwrap_err();
+        assert!(error.to_string().contains("Unexpected token"));
+    }
+
+    #[test]
+    fn test_parse_doc_comment() {
+        let mut input = TokenStream::from_str(concat!(
+            r#"#[doc = " Summary line"] #[doc = " continues."] #[doc = ""]"#,
+            r#"#[doc = "     let x = 1;"] #[doc = ""]"#,
+            r#"#[doc = "\n * Block\n * text\n "] #[doc = include_str!("README.md")]"#,
+        ))
+        .unwrap()
+        .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+        let doc = parse_doc_comment(&attrs);
+
+        assert_eq!(
+            doc.text(),
+            "Summary line\ncontinues.\n\n    let x = 1;\n\nBlock\ntext"
+        );
+        assert_eq!(doc.summary(), "Summary line continues.");
+        assert_eq!(doc.body(), "    let x = 1;\n\nBlock\ntext");
+        assert_eq!(doc.paragraphs().len(), 3);
+        assert_eq!(doc.macros.len(), 1);
+
+        let mut output = doc.to_token_stream().into_token_iter();
+        let reparsed = parse_doc_comment(&output.parse_attributes().unwrap());
+        assert_eq!(reparsed.text(), doc.text());
+        assert_eq!(reparsed.macros.len(), 1);
+    }
+
+    #[test]
+    fn test_markdown_to_text() {
+        let markdown = [
+            "# Usage",
+            "",
+            "Set the **output** `path`, see [`Config`] and [the docs](https://docs.rs/).",
+            "Works with snake_case names, *emphasis*, ~~old~~ and <https://example.com>.",
+            "",
+            "* First [item][ref]",
+            "  + Nested \\*literal\\*",
+            "Index foo[0] with a*b, [default: 5] and ![logo](logo.png)!",
+            "Shortcut [ref] and [`Foo`], 2 * 3 * 4 and __init__ or _x_.",
+            "1. Numbered",
+            "",
+            "```",
+            "# fn main() {",
+            "let x = [1, 2];",
+            "# }",
+            "```",
+            "",
+            "```text",
+            "# Not hidden",
+            "```",
+            "",
+            "[ref]: https://example.com",
+        ]
+        .join("\n");
+
+        let expected = [
+            "Usage",
+            "",
+            "Set the output path, see Config and the docs.",
+            "Works with snake_case names, emphasis, old and https://example.com.",
+            "",
+            "- First item",
+            "  - Nested *literal*",
+            "Index foo[0] with a*b, [default: 5] and logo!",
+            "Shortcut ref and Foo, 2 * 3 * 4 and init or x.",
+            "1. Numbered",
+            "",
+            "    let x = [1, 2];",
+            "",
+            "    # Not hidden",
+            "",
+        ]
+        .join("\n");
+
+        assert_eq!(markdown_to_text(&markdown), expected);
+    }
+
+    #[test]
+    fn test_cfg_attrs() {
+        let mut input = TokenStream::from_str(concat!(
+            r#"#[cfg(feature = "std")] #[doc = "Foo"]"#,
+            r#"#[cfg_attr(feature = "serde", myattr(rename = "bar"), cfg_attr(test, other))]"#,
+        ))
+        .unwrap()
+        .into_token_iter();
+        let attrs = input.parse_attributes().unwrap();
+
+        let cfgs = get_cfg_attrs(&attrs);
+        assert_eq!(cfgs.len(), 1);
+        assert!(matches!(
+            parse_cfg(cfgs[0]).unwrap(),
+            CfgPredicate::KeyValue(..)
+        ));
+
+        let cfg_attr = parse_cfg_attr(&attrs[2]).unwrap();
+        assert_eq!(cfg_attr.attrs.len(), 2);
+        assert_eq!(
+            cfg_attr.to_token_stream().to_string(),
+            concat!(
+                r#"# [cfg_attr (feature = "serde" , myattr (rename = "bar") , "#,
+                "cfg_attr (test , other))]",
+            ),
+        );
+
+        let names = expand_cfg_attrs(&attrs)
+            .unwrap()
+            .iter()
+            .map(|attr| attr.name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["cfg", "doc", "myattr", "other"]);
+    }
+
+    #[test]
+    fn test_parse_repr() {
+        let parse = |input: &str| {
+            let mut input = TokenStream::from_str(input).unwrap().into_token_iter();
+            parse_repr(&input.parse_attributes().unwrap())
+        };
+
+        let repr = parse("#[repr(u8)]").unwrap();
+        assert_eq!(repr.int.map(|(int, _)| int), Some(IntType::U8));
+
+        let repr = parse("#[repr(C, packed(2))] #[repr(i32)]").unwrap();
+        assert!(repr.c.is_some());
+        assert_eq!(repr.packed.map(|(align, _)| align), Some(2));
+        assert_eq!(repr.int.map(|(int, _)| int), Some(IntType::I32));
+
+        assert_eq!(
+            repr.to_token_stream().to_string(),
+            "# [repr (i32 , C , packed (2))]"
+        );
+
+        let repr = parse("#[repr(packed)]").unwrap();
+        assert_eq!(repr.packed.map(|(align, _)| align), Some(1));
+        assert_eq!(repr.to_token_stream().to_string(), "# [repr (packed)]");
+        assert!(Repr::default().to_token_stream().is_empty());
+
+        let repr = parse("#[repr(transparent)]").unwrap();
+        assert!(repr.transparent.is_some());
+
+        assert!(parse("#[repr(u8, u16)]").is_err());
+        assert!(parse("#[repr(transparent, C)]").is_err());
+        assert!(parse("#[repr(packed, align(4))]").is_err());
+        assert!(parse("#[repr(align(3))]").is_err());
+        assert!(parse("#[repr(simd)]").is_err());
+    }
+
+    #[test]
+    fn test_eval_discriminant() {
+        let variants = [("A".to_string(), 4)];
+        let eval_as = |input: &str, int: IntType| {
+            eval_discriminant(
+                TokenStream::from_str(input).unwrap(),
+                "Enum",
+                &variants,
+                int,
+            )
+        };
+        let eval = |input: &str| eval_as(input, IntType::Isize);
+
+        assert_eq!(eval("1 + 2 * 3").unwrap(), 7);
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), 9);
+        assert_eq!(eval("1 << 4 | 0x0f & !0").unwrap(), 31);
+        assert_eq!(eval("-1 - -2").unwrap(), 1);
+        assert_eq!(eval("0b1010 ^ 0o7 >> 1").unwrap(), 9);
+        assert_eq!(eval("Self::A as isize + Enum::A").unwrap(), 8);
+        assert_eq!(eval("-1 as u8").unwrap(), 255);
+        assert_eq!(eval_as("1_000u16 % 7", IntType::U16).unwrap(), 6);
+        assert!(eval("FOO + 1").is_err());
+        assert!(eval("A").is_err());
+        assert!(eval("Other::A").is_err());
+        assert!(eval("u8::A").is_err());
+        assert!(eval("foo()").is_err());
+        assert!(eval("1 / 0").is_err());
+        assert!(eval("1 1").is_err());
+        assert!(eval("1u8").is_err());
+
+        // Operators use the width and signedness of the type.
+        assert_eq!(eval_as("!0", IntType::U8).unwrap(), 255);
+        assert_eq!(eval_as("!0", IntType::I8).unwrap(), -1);
+        assert_eq!(eval_as("!1 >> 4", IntType::U8).unwrap(), 15);
+        assert_eq!(eval_as("0x81 << 1", IntType::U8).unwrap(), 2);
+        assert_eq!(eval_as("-128", IntType::I8).unwrap(), -128);
+        assert_eq!(eval_as("-1 as u8", IntType::U8).unwrap(), 255);
+        assert_eq!(eval_as("200 as i8", IntType::I8).unwrap(), -56);
+        assert!(eval_as("1 << 8", IntType::U8).is_err());
+        assert!(eval_as("255 + 1", IntType::U8).is_err());
+        assert!(eval_as("0 - 1", IntType::U8).is_err());
+        assert!(eval_as("-1", IntType::U8).is_err());
+        assert!(eval_as("256", IntType::U8).is_err());
+        assert!(eval_as("128", IntType::I8).is_err());
+        assert!(eval_as("-(128)", IntType::I8).is_err());
+    }
+
+    #[test]
+    fn test_eval_discriminants() {
+        let variant = |name: &str, expr: Option<&str>| {
+            (
+                Ident::new(name, Span::call_site()),
+                expr.map(|expr| TokenStream::from_str(expr).unwrap()),
+            )
+        };
+        let name = Ident::new("Enum", Span::call_site());
+        let mut repr = Repr::default();
+
+        let variants = [
+            variant("A", None),
+            variant("B", Some("10")),
+            variant("C", None),
+            variant("D", Some("Self::B * 2 + Enum::A")),
+            variant("E", Some("FOO")),
+            variant("F", None),
+            variant("G", Some("-1")),
+            variant("H", None),
+        ];
+        assert_eq!(
+            eval_discriminants(&name, &variants, &repr).unwrap(),
+            [
+                Some(0),
+                Some(10),
+                Some(11),
+                Some(20),
+                None,
+                None,
+                Some(-1),
+                Some(0)
+            ]
+        );
+
+        repr.int = Some((IntType::U8, Span::call_site()));
+        assert!(eval_discriminants(&name, &variants, &repr).is_err());
+
+        let variants = [variant("A", Some("255")), variant("B", None)];
+        assert!(eval_discriminants(&name, &variants, &repr).is_err());
+
+        let variants = [
+            variant("A", Some("!0")),
+            variant("B", Some("A")),
+            variant("C", Some("Other::A")),
+        ];
+        assert_eq!(
+            eval_discriminants(&name, &variants, &repr).unwrap(),
+            [Some(255), None, None]
+        );
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let stream = TokenStream::from_str("a b").unwrap();
+        assert_eq!(parse_stream::<Vec<Ident>>(stream).unwrap().len(), 2);
+
+        let stream = TokenStream::from_str("a b").unwrap();
+        let error = parse_stream::<Ident>(stream).unwrap_err();
+        assert!(error.to_string().contains("Unexpected token"));
+
+        assert!(parse_stream::<Ident>(TokenStream::new()).is_err());
+    }
+
+    #[test]
+    fn test_token_builder_spacing() {
+        let spacing = TokenBuilder::new()
+            .punct_joint("::")
+            .punct_joint("..=")
+            .punct('!')
+            .build()
+            .into_token_iter()
+            .filter_map(|tree| match tree {
+                TokenTree::Punct(punct) => Some(punct.spacing()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spacing,
+            [
+                Spacing::Joint,
+                Spacing::Alone,
+                Spacing::Joint,
+                Spacing::Joint,
+                Spacing::Alone,
+                Spacing::Alone,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty operator")]
+    fn test_token_builder_empty_op() {
+        let _ = TokenBuilder::new().punct_joint("");
+    }
+}